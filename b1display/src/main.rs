@@ -49,7 +49,11 @@ use usbd_serial::{SerialPort, USB_CLASS_CDC};
 use core::fmt::Write;
 use heapless::String;
 
+use fl16_inputmodules::clock::{ClockFace, DeviceTime};
 use fl16_inputmodules::control::*;
+use fl16_inputmodules::dashboard::Dashboard;
+use fl16_inputmodules::framebuffer::{Framebuffer, Mirrored};
+use fl16_inputmodules::screensaver::{Bitmap, IdleProtection, ScreenSaverConfig, ScreenSaverMode};
 
 /// Wrapper around cortex_m::delay::Delay that implements embedded-hal 1.0's DelayNs
 struct Delay(cortex_m::delay::Delay);
@@ -97,6 +101,17 @@ type B1ST7306 = ST7306<
 
 const DEBUG: bool = false;
 const SCRNS_DELTA: i32 = 5;
+/// Animation ticks between moving the content, in the pixel-shift screensaver
+const PIXEL_SHIFT_TICKS: u32 = 60;
+/// Offsets that the pixel-shift screensaver moves the content through
+const PIXEL_SHIFT_OFFSETS: [Point; 4] = [
+    Point::new(0, 0),
+    Point::new(2, 0),
+    Point::new(2, 2),
+    Point::new(0, 2),
+];
+/// Animation ticks between inverting the content and back, in the invert screensaver
+const INVERT_TICKS: u32 = 60;
 const WIDTH: i32 = 300;
 const HEIGHT: i32 = 400;
 const SIZE: Size = Size::new(WIDTH as u32, HEIGHT as u32);
@@ -191,13 +206,23 @@ fn main() -> ! {
         sleeping: SimpleSleepState::Awake,
         screen_inverted: false,
         screen_on: true,
-        screensaver: Some(ScreenSaverState::default()),
+        screensaver: None,
+        screensaver_config: ScreenSaverConfig::default(),
+        // Start the screensaver right after booting
+        screensaver_due: Some(0),
+        idle_protection: IdleProtection::default(),
+        idle_since: 0,
+        bitmap: Bitmap::default(),
+        framebuffer: Framebuffer::default(),
         power_mode: PowerMode::Lpm,
         fps_config: FpsConfig {
             hpm: HpmFps::ThirtyTwo,
             lpm: LpmFps::Two,
         },
         animation_period: 1_000_000, // 1000ms = 1Hz
+        dashboard: Dashboard::default(),
+        time: None,
+        clock: None,
     };
 
     const INVERTED: bool = false;
@@ -228,7 +253,11 @@ fn main() -> ! {
         .draw(&mut disp)
         .unwrap();
 
-    let logo_rect = draw_logo(&mut disp, Point::new(LOGO_OFFSET_X, LOGO_OFFSET_Y)).unwrap();
+    let logo_rect = draw_logo(
+        &mut Mirrored::new(&mut disp, &mut state.framebuffer),
+        Point::new(LOGO_OFFSET_X, LOGO_OFFSET_Y),
+    )
+    .unwrap();
     if DEBUG {
         Rectangle::new(Point::new(10, 10), Size::new(10, 10))
             .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
@@ -269,67 +298,170 @@ fn main() -> ! {
     loop {
         // Go to sleep if the host is sleeping
         let host_sleeping = sleep.is_low().unwrap();
-        handle_sleep(host_sleeping, &mut state, &mut delay, &mut disp);
+        let now = timer.get_counter().ticks();
+        handle_sleep(host_sleeping, now, &mut state, &mut delay, &mut disp);
 
         // Handle period display updates. Don't do it too often
         if timer.get_counter().ticks() > prev_timer + state.animation_period {
             prev_timer = timer.get_counter().ticks();
 
+            if let Some(due) = state.screensaver_due {
+                if prev_timer >= due {
+                    state.screensaver_due = None;
+                    state.screensaver = Some(ScreenSaverState::new(state.screensaver_config.mode));
+                }
+            }
+
+            // Protect against burn-in, when the content hasn't changed for a while
+            // The clock changes by itself, so it doesn't need that
+            let idle = state.idle_protection;
+            if idle.timeout > 0
+                && state.screensaver.is_none()
+                && state.screensaver_due.is_none()
+                && state.clock.is_none()
+                && matches!(state.sleeping, SimpleSleepState::Awake)
+                && prev_timer >= state.idle_since + idle.timeout as u64 * 1_000_000
+            {
+                state.screensaver = Some(ScreenSaverState::new(idle.mode));
+            }
+
             if let Some(ref mut screensaver) = state.screensaver {
-                let seconds = ticks / (1_000_000 / state.animation_period);
-                #[allow(clippy::modulo_one)]
-                let second_decimals = ticks % (1_000_000 / state.animation_period);
-                Rectangle::new(Point::new(0, 0), Size::new(300, 50))
-                    .into_styled(PrimitiveStyle::with_fill(Rgb565::WHITE))
-                    .draw(&mut disp)
-                    .unwrap();
-                let mut text: String<32> = String::new();
-                write!(
-                    &mut text,
-                    "{:>4} Ticks ({:>4}.{} s)",
-                    ticks, seconds, second_decimals
-                )
-                .unwrap();
-                // Uncomment to draw the ticks on the screen
-                //draw_text(
-                //    &mut disp,
-                //    &text,
-                //    Point::new(0, 0),
-                //).unwrap();
-                ticks += 1;
-
-                logo_pos = {
-                    let (x, y) = (logo_pos.x, logo_pos.y);
-                    let w = logo_rect.size.width as i32;
-                    let h = logo_rect.size.height as i32;
-
-                    // Bounce off the walls
-                    if x <= 0 || x + w >= WIDTH {
-                        screensaver.rightwards *= -1;
+                // Nothing of the content should be left, except when it's shifted or inverted
+                let first_tick = screensaver.ticks == 0;
+                let screen = Rectangle::new(Point::new(0, 0), SIZE);
+
+                match screensaver.mode {
+                    mode @ (ScreenSaverMode::Logo | ScreenSaverMode::Bitmap) => {
+                        if first_tick {
+                            clear_region(&mut disp, screen, Rgb565::WHITE).unwrap();
+                        }
+
+                        let seconds = ticks / (1_000_000 / state.animation_period);
+                        #[allow(clippy::modulo_one)]
+                        let second_decimals = ticks % (1_000_000 / state.animation_period);
+                        Rectangle::new(Point::new(0, 0), Size::new(300, 50))
+                            .into_styled(PrimitiveStyle::with_fill(Rgb565::WHITE))
+                            .draw(&mut disp)
+                            .unwrap();
+                        let mut text: String<32> = String::new();
+                        write!(
+                            &mut text,
+                            "{:>4} Ticks ({:>4}.{} s)",
+                            ticks, seconds, second_decimals
+                        )
+                        .unwrap();
+                        // Uncomment to draw the ticks on the screen
+                        //draw_text(
+                        //    &mut disp,
+                        //    &text,
+                        //    Point::new(0, 0),
+                        //).unwrap();
+                        ticks += 1;
+
+                        // Bounce the uploaded bitmap, if there is one
+                        let bitmap = Some(&state.bitmap)
+                            .filter(|bitmap| mode == ScreenSaverMode::Bitmap && !bitmap.is_empty());
+                        let size = bitmap.map_or(logo_rect.size, |bitmap| bitmap.size());
+
+                        logo_pos = {
+                            let (x, y) = (logo_pos.x, logo_pos.y);
+                            let w = size.width as i32;
+                            let h = size.height as i32;
+
+                            // Bounce off the walls
+                            if x <= 0 || x + w >= WIDTH {
+                                screensaver.rightwards *= -1;
+                            }
+                            if y <= 0 || y + h >= HEIGHT {
+                                screensaver.downwards *= -1;
+                            }
+
+                            Point::new(
+                                x + screensaver.rightwards * SCRNS_DELTA,
+                                y + screensaver.downwards * SCRNS_DELTA,
+                            )
+                        };
+                        // Draw a border around the new logo, to clear previously drawn adjacent logos
+                        let style = PrimitiveStyleBuilder::new()
+                            .stroke_color(Rgb565::WHITE)
+                            .stroke_width(2 * SCRNS_DELTA as u32)
+                            .build();
+                        Rectangle::new(
+                            logo_pos - Point::new(SCRNS_DELTA, SCRNS_DELTA),
+                            size + Size::new(2 * SCRNS_DELTA as u32, 2 * SCRNS_DELTA as u32),
+                        )
+                        .into_styled(style)
+                        .draw(&mut disp)
+                        .unwrap();
+                        match bitmap {
+                            Some(bitmap) => bitmap.draw(&mut disp, logo_pos).unwrap(),
+                            None => {
+                                draw_logo(&mut disp, logo_pos).unwrap();
+                            }
+                        }
+                        disp.flush().unwrap();
                     }
-                    if y <= 0 || y + h >= HEIGHT {
-                        screensaver.downwards *= -1;
+                    ScreenSaverMode::Clock => {
+                        let time = state.time.map(|time| {
+                            let (hours, minutes, _) = time.time_of_day(prev_timer);
+                            (hours, minutes)
+                        });
+                        // Only redraw when the time that's shown changes
+                        if first_tick || time != screensaver.drawn_time {
+                            match time {
+                                Some((hours, minutes)) => {
+                                    draw_digital_clock(&mut disp, hours, minutes).unwrap()
+                                }
+                                None => clear_region(&mut disp, screen, Rgb565::WHITE).unwrap(),
+                            }
+                            disp.flush().unwrap();
+                            screensaver.drawn_time = time;
+                        }
                     }
+                    ScreenSaverMode::PixelShift => {
+                        if screensaver.ticks % PIXEL_SHIFT_TICKS == 0 {
+                            let step = screensaver.ticks / PIXEL_SHIFT_TICKS;
+                            let offset =
+                                PIXEL_SHIFT_OFFSETS[step as usize % PIXEL_SHIFT_OFFSETS.len()];
+                            disp.draw_pixels(state.framebuffer.shifted(offset), false)
+                                .unwrap();
+                            disp.flush().unwrap();
+                        }
+                    }
+                    ScreenSaverMode::Invert => {
+                        if screensaver.ticks % INVERT_TICKS == 0 {
+                            // Every other time, the colors are the other way around
+                            let step = screensaver.ticks / INVERT_TICKS;
+                            disp.invert_screen(state.screen_inverted != (step % 2 == 1))
+                                .unwrap();
+                        }
+                    }
+                    ScreenSaverMode::Blank => {
+                        if first_tick {
+                            clear_region(&mut disp, screen, Rgb565::WHITE).unwrap();
+                            disp.flush().unwrap();
+                        }
+                    }
+                }
+                screensaver.ticks += 1;
+            }
 
-                    Point::new(
-                        x + screensaver.rightwards * SCRNS_DELTA,
-                        y + screensaver.downwards * SCRNS_DELTA,
-                    )
-                };
-                // Draw a border around the new logo, to clear previously drawn adjacent logos
-                let style = PrimitiveStyleBuilder::new()
-                    .stroke_color(Rgb565::WHITE)
-                    .stroke_width(2 * SCRNS_DELTA as u32)
-                    .build();
-                Rectangle::new(
-                    logo_pos - Point::new(SCRNS_DELTA, SCRNS_DELTA),
-                    logo_rect.size + Size::new(2 * SCRNS_DELTA as u32, 2 * SCRNS_DELTA as u32),
-                )
-                .into_styled(style)
-                .draw(&mut disp)
-                .unwrap();
-                draw_logo(&mut disp, logo_pos).unwrap();
-                disp.flush().unwrap();
+            if let (Some(clock), Some(time), SimpleSleepState::Awake) =
+                (&mut state.clock, state.time, &state.sleeping)
+            {
+                let (hours, minutes, _) = time.time_of_day(timer.get_counter().ticks());
+                // Only redraw when the time that's shown changes
+                if clock.drawn != Some((hours, minutes)) {
+                    let mut target = Mirrored::new(&mut disp, &mut state.framebuffer);
+                    match clock.face {
+                        ClockFace::Analog => {
+                            draw_analog_clock(&mut target, hours, minutes).unwrap()
+                        }
+                        _ => draw_digital_clock(&mut target, hours, minutes).unwrap(),
+                    }
+                    disp.flush().unwrap();
+                    clock.drawn = Some((hours, minutes));
+                }
             }
         }
 
@@ -346,7 +478,8 @@ fn main() -> ! {
                 Ok(count) => {
                     match (parse_command(count, &buf), &state.sleeping) {
                         (Some(Command::Sleep(go_sleeping)), _) => {
-                            handle_sleep(go_sleeping, &mut state, &mut delay, &mut disp);
+                            let now = timer.get_counter().ticks();
+                            handle_sleep(go_sleeping, now, &mut state, &mut delay, &mut disp);
                         }
                         (Some(c @ Command::BootloaderReset), _)
                         | (Some(c @ Command::IsSleeping), _) => {
@@ -358,6 +491,13 @@ fn main() -> ! {
                         }
                         (Some(command), SimpleSleepState::Awake) => {
                             // While sleeping no command is handled, except waking up
+                            if let Command::SetTime(hours, minutes, seconds) = command {
+                                let now = timer.get_counter().ticks();
+                                state.time = Some(DeviceTime::new(hours, minutes, seconds, now));
+                            }
+                            if command.is_activity() {
+                                state.idle_since = timer.get_counter().ticks();
+                            }
                             if let Some(response) = handle_command(
                                 &command, &mut state, logo_rect, &mut disp, &mut delay,
                             ) {
@@ -384,6 +524,7 @@ fn main() -> ! {
 
 fn handle_sleep<SPI, DC, RST, DELAY, const COLS: usize, const ROWS: usize>(
     go_sleeping: bool,
+    now: u64,
     state: &mut B1DIsplayState,
     delay: &mut DELAY,
     disp: &mut ST7306<SPI, DC, RST, COLS, ROWS>,
@@ -420,9 +561,13 @@ fn handle_sleep<SPI, DC, RST, DELAY, const COLS: usize, const ROWS: usize>(
                 disp.switch_mode(delay, PowerMode::Lpm).unwrap();
             }
 
-            // Turn screensaver on when resuming from sleep
-            // TODO Subject to change, but currently I want to avoid burn-in by default
-            state.screensaver = Some(ScreenSaverState::default());
+            // Turn screensaver on after resuming from sleep, to avoid burn-in by default
+            // Unless the host turned that off or wants to show the clock
+            state.idle_since = now;
+            let config = state.screensaver_config;
+            if config.on_wake && state.clock.is_none() && state.screensaver.is_none() {
+                state.screensaver_due = Some(now + config.timeout as u64 * 1_000_000);
+            }
 
             // TODO: Power display controller back on
         }
@@ -19,6 +19,7 @@ pub enum Animation {
     Breathing(BreathingIterator),
     Snake(SnakeIterator),
     Pong(PongIterator),
+    Scroll(ScrollIterator),
 }
 impl Iterator for Animation {
     type Item = Grid;
@@ -31,6 +32,7 @@ impl Iterator for Animation {
             Animation::Breathing(x) => x.next(),
             Animation::Snake(x) => x.next(),
             Animation::Pong(x) => x.next(),
+            Animation::Scroll(x) => x.next(),
         }
     }
 }
@@ -106,7 +108,7 @@ impl GameOfLifeIterator {
     pub fn new(start_param: GameOfLifeStartParam, frames: usize) -> Self {
         Self {
             // Could start with a custom grid
-            state: GameOfLifeState::new(start_param, &Grid::default()),
+            state: GameOfLifeState::new(&GameOfLifeOptions::new(start_param), &Grid::default()),
             frames_remaining: frames,
         }
     }
@@ -169,3 +171,100 @@ impl Iterator for BreathingIterator {
         }
     }
 }
+
+/// Maximum number of rows that can be scrolled through
+pub const SCROLL_STRIP_ROWS: usize = 512;
+/// Rows that fit into a single command, next to the 2 byte offset
+pub const SCROLL_ROWS_PER_COMMAND: usize = 29;
+
+/// Black/white image that's much taller than the display, for scrolling through
+/// Each row has one bit per column, bit 0 is the leftmost column.
+#[derive(Clone)]
+pub struct ScrollStrip([u16; SCROLL_STRIP_ROWS]);
+
+impl Default for ScrollStrip {
+    fn default() -> Self {
+        ScrollStrip([0; SCROLL_STRIP_ROWS])
+    }
+}
+
+impl ScrollStrip {
+    /// Overwrite rows starting at offset. Rows past the end are ignored
+    pub fn stage(&mut self, offset: usize, rows: &[u16]) {
+        for (i, row) in rows.iter().enumerate() {
+            if let Some(dest) = self.0.get_mut(offset + i) {
+                *dest = *row;
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ScrollParams {
+    /// Number of rows of the strip to scroll through
+    pub len: usize,
+    /// How many animation frames to wait before moving by one row
+    pub frames_per_step: u8,
+    /// Start from the beginning again after reaching the end
+    pub looping: bool,
+    /// New rows come in at the top, instead of the bottom
+    pub from_top: bool,
+}
+
+pub struct ScrollIterator {
+    strip: ScrollStrip,
+    params: ScrollParams,
+    offset: usize,
+    current_frame: u8,
+}
+
+impl ScrollIterator {
+    pub fn new(strip: ScrollStrip, params: ScrollParams) -> Self {
+        Self {
+            strip,
+            params: ScrollParams {
+                len: params.len.clamp(1, SCROLL_STRIP_ROWS),
+                frames_per_step: params.frames_per_step.max(1),
+                ..params
+            },
+            offset: 0,
+            current_frame: 0,
+        }
+    }
+}
+
+impl Iterator for ScrollIterator {
+    type Item = Grid;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.params.len {
+            return None;
+        }
+
+        let mut grid = Grid::default();
+        for y in 0..HEIGHT {
+            let row = if self.params.from_top {
+                self.offset + HEIGHT - 1 - y
+            } else {
+                self.offset + y
+            };
+            let bits = self.strip.0[row % self.params.len];
+            for x in 0..WIDTH {
+                if bits & (1 << x) != 0 {
+                    grid.0[WIDTH - 1 - x][y] = 0xFF;
+                }
+            }
+        }
+
+        self.current_frame += 1;
+        if self.current_frame >= self.params.frames_per_step {
+            self.current_frame = 0;
+            self.offset += 1;
+            if self.params.looping {
+                self.offset %= self.params.len;
+            }
+        }
+
+        Some(grid)
+    }
+}
@@ -0,0 +1,90 @@
+//! Clock that keeps running on the device, after the host set the time
+//!
+//! The time is kept with the RP2040 timer, which counts microseconds since
+//! boot. It drifts a little, so the host should set the time again once in a
+//! while.
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// How to show the clock
+#[derive(Copy, Clone, PartialEq, num_derive::FromPrimitive)]
+pub enum ClockFace {
+    /// Hours and minutes in digits
+    Digital = 0x01,
+    /// Hands on a dial. Only on the B1 display, the LED matrix shows digits instead
+    Analog = 0x02,
+    /// Hours and minutes in digits, rotated to read the LED matrix sideways
+    Landscape = 0x03,
+}
+
+/// Time of day that was set by the host
+#[derive(Copy, Clone)]
+pub struct DeviceTime {
+    /// Seconds since midnight, when the time was set
+    seconds: u64,
+    /// Timer ticks in microseconds, when the time was set
+    set_at: u64,
+}
+
+impl DeviceTime {
+    pub fn new(hours: u8, minutes: u8, seconds: u8, now: u64) -> Self {
+        DeviceTime {
+            seconds: (hours as u64 * 60 + minutes as u64) * 60 + seconds as u64,
+            set_at: now,
+        }
+    }
+
+    /// Hours, minutes and seconds at the given timer ticks
+    pub fn time_of_day(&self, now: u64) -> (u8, u8, u8) {
+        let elapsed = now.saturating_sub(self.set_at) / 1_000_000;
+        let seconds = (self.seconds + elapsed) % SECONDS_PER_DAY;
+        (
+            (seconds / 3600) as u8,
+            (seconds / 60 % 60) as u8,
+            (seconds % 60) as u8,
+        )
+    }
+}
+
+/// Clock that's currently shown
+#[derive(Copy, Clone)]
+pub struct ClockState {
+    pub face: ClockFace,
+    /// Hours and minutes that were last drawn, to only redraw when they change
+    pub drawn: Option<(u8, u8)>,
+}
+
+/// Digits 0-9 and a colon, 5x6 pixels each
+/// Each row is a byte, the leftmost pixel is the highest of the 5 bits.
+pub const DIGIT_GLYPHS: [[u8; 6]; 11] = [
+    [0b01110, 0b10001, 0b10101, 0b10101, 0b10001, 0b01110],
+    [0b00100, 0b01100, 0b10100, 0b00100, 0b00100, 0b11111],
+    [0b11110, 0b00001, 0b11111, 0b10000, 0b10000, 0b11111],
+    [0b11110, 0b00001, 0b11111, 0b00001, 0b00001, 0b11110],
+    [0b00010, 0b00110, 0b01010, 0b11111, 0b00010, 0b00010],
+    [0b11111, 0b10000, 0b11111, 0b00001, 0b00001, 0b11110],
+    [0b01110, 0b10000, 0b11111, 0b10001, 0b10001, 0b01110],
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b00100, 0b00100],
+    [0b01110, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+    [0b01110, 0b10001, 0b11111, 0b00001, 0b00001, 0b01110],
+    [0b00000, 0b00000, 0b00100, 0b00000, 0b00100, 0b00000],
+];
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 6;
+const COLON: usize = 10;
+
+/// Glyphs of HH:MM
+pub fn clock_glyphs(hours: u8, minutes: u8) -> [&'static [u8; 6]; 5] {
+    [
+        &DIGIT_GLYPHS[(hours / 10 % 10) as usize],
+        &DIGIT_GLYPHS[(hours % 10) as usize],
+        &DIGIT_GLYPHS[COLON],
+        &DIGIT_GLYPHS[(minutes / 10 % 10) as usize],
+        &DIGIT_GLYPHS[(minutes % 10) as usize],
+    ]
+}
+
+/// Whether the pixel of a glyph is on
+pub fn glyph_pixel(glyph: &[u8; 6], x: usize, y: usize) -> bool {
+    glyph[y] & (1 << (GLYPH_WIDTH - 1 - x)) != 0
+}
@@ -4,17 +4,29 @@ use rp2040_hal::rom_data::reset_to_usb_boot;
 
 use crate::serialnum::{device_release, is_pre_release};
 
+#[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+use crate::clock::*;
+
+#[cfg(feature = "b1display")]
+use crate::dashboard::*;
+#[cfg(feature = "b1display")]
+use crate::framebuffer::*;
 #[cfg(feature = "b1display")]
 use crate::graphics::*;
 #[cfg(feature = "b1display")]
+use crate::rle::*;
+#[cfg(feature = "b1display")]
+use crate::screensaver::*;
+#[cfg(feature = "b1display")]
 use core::fmt::Write;
 #[cfg(feature = "b1display")]
 use embedded_graphics::Pixel;
 #[cfg(feature = "b1display")]
 use embedded_graphics::{
     pixelcolor::Rgb565,
-    prelude::{Point, RgbColor},
-    primitives::Rectangle,
+    prelude::{Dimensions, Point, RgbColor, Size},
+    primitives::{PointsIter, Rectangle},
+    text::Alignment,
 };
 #[cfg(feature = "b1display")]
 use embedded_hal::delay::DelayNs;
@@ -27,14 +39,22 @@ use heapless::String;
 #[cfg(feature = "b1display")]
 use st7306::{FpsConfig, PowerMode, ST7306};
 
+#[cfg(feature = "ledmatrix")]
+use crate::animations::*;
+#[cfg(feature = "ledmatrix")]
+use crate::games;
 #[cfg(feature = "ledmatrix")]
 use crate::games::pong;
 #[cfg(feature = "ledmatrix")]
 use crate::games::snake;
 #[cfg(feature = "ledmatrix")]
+use crate::games::tetris;
+#[cfg(feature = "ledmatrix")]
 use crate::matrix::*;
 #[cfg(feature = "ledmatrix")]
 use crate::patterns::*;
+#[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+use heapless::Vec;
 #[cfg(feature = "ledmatrix")]
 use is31fl3741::PwmFreq;
 
@@ -71,6 +91,24 @@ pub enum CommandVals {
     PwmFreq = 0x1E,
     DebugMode = 0x1F,
     Version = 0x20,
+    StageScrollRows = 0x21,
+    StartScroll = 0x22,
+    BlitRect = 0x23,
+    RleFramebuffer = 0x24,
+    DrawText = 0x25,
+    ClearRegion = 0x26,
+    SetWidget = 0x27,
+    UpdateWidget = 0x28,
+    SetTime = 0x29,
+    ClockMode = 0x2A,
+    ScreenSaverMode = 0x2B,
+    UploadBitmap = 0x2C,
+    IdleProtection = 0x2D,
+    GetFramebuffer = 0x2E,
+    GetGrid = 0x2F,
+    Present = 0x30,
+    GameSpeed = 0x31,
+    HighScores = 0x32,
 }
 
 #[derive(num_derive::FromPrimitive)]
@@ -86,10 +124,30 @@ pub enum PatternVals {
 }
 
 pub enum Game {
-    Snake,
-    Pong,
+    Snake(SnakeStartParam),
+    Pong(PongStartParam),
     Tetris,
-    GameOfLife(GameOfLifeStartParam),
+    GameOfLife(GameOfLifeOptions),
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct SnakeStartParam {
+    /// Leave on one side, come back on the other, instead of hitting the edge
+    pub wrap: bool,
+    /// Speed level to start at
+    pub speed: u8,
+    /// Speed levels gained with every food
+    pub speed_up: u8,
+    /// Number of obstacles in the way
+    pub obstacles: u8,
+}
+
+#[derive(Copy, Clone)]
+pub struct PongStartParam {
+    /// Points that win the match. 0 to keep playing forever
+    pub points_to_win: u8,
+    /// With fewer than 2 players, the module moves the lower paddle
+    pub players: u8,
 }
 
 #[derive(Copy, Clone, num_derive::FromPrimitive)]
@@ -109,6 +167,9 @@ pub enum GameControlArg {
     Exit = 4,
     SecondLeft = 5,
     SecondRight = 6,
+    Pause = 7,
+    Resume = 8,
+    Restart = 9,
 }
 
 #[derive(Copy, Clone, num_derive::FromPrimitive)]
@@ -120,6 +181,57 @@ pub enum GameOfLifeStartParam {
     Beacon = 0x04,
     Glider = 0x05,
     BeaconToadBlinker = 0x06,
+    /// Cells uploaded together with the start command
+    Bitmap = 0x07,
+}
+
+/// Bytes of an uploaded seed, in the same layout as Draw
+pub const SEED_BYTES: usize = 39;
+
+/// Life-like rules, which neighbor counts let a cell be born or survive
+/// Bit n is set, if n neighbors are enough.
+#[derive(Copy, Clone)]
+pub struct LifeRules {
+    pub birth: u16,
+    pub survive: u16,
+}
+
+impl Default for LifeRules {
+    /// Conway's Game of Life, B3/S23
+    fn default() -> Self {
+        LifeRules {
+            birth: 1 << 3,
+            survive: (1 << 2) | (1 << 3),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct GameOfLifeOptions {
+    pub pattern: GameOfLifeStartParam,
+    pub rules: LifeRules,
+    /// Whether the board wraps around the edges. Otherwise everything beyond is dead
+    pub wrap: bool,
+    /// Start over from a random soup, when it's dead, still or oscillating
+    pub auto_restart: bool,
+    /// Show older cells dimmer than newborn ones
+    pub show_age: bool,
+    /// Cells to start from, with the Bitmap pattern
+    pub seed: [u8; SEED_BYTES],
+}
+
+impl GameOfLifeOptions {
+    /// Classic rules on a board that wraps around
+    pub fn new(pattern: GameOfLifeStartParam) -> Self {
+        GameOfLifeOptions {
+            pattern,
+            rules: LifeRules::default(),
+            wrap: true,
+            auto_restart: false,
+            show_age: false,
+            seed: [0; SEED_BYTES],
+        }
+    }
 }
 
 #[derive(Copy, Clone, num_derive::FromPrimitive)]
@@ -194,10 +306,44 @@ pub enum Command {
     InvertScreen(bool),
     GetInvertScreen,
     SetPixelColumn(usize, [u8; 50]),
+    /// Draw black/white pixels into a rectangle, row by row. Optionally flush
+    #[cfg(feature = "b1display")]
+    BlitRect(Rectangle, [u8; BLIT_BYTES], bool),
+    /// Write run-length encoded bytes into the framebuffer, starting at the offset
+    #[cfg(feature = "b1display")]
+    RleFramebuffer(usize, [u8; RLE_BYTES], usize),
+    /// Draw text with a font and layout. Optionally flush
+    #[cfg(feature = "b1display")]
+    DrawText(TextLayout, String<TEXT_BYTES>, bool),
+    /// Fill a rectangle with black or white. Optionally flush
+    #[cfg(feature = "b1display")]
+    ClearRegion(Rectangle, bool, bool),
+    /// Add, replace or remove a dashboard widget by ID. Optionally flush
+    #[cfg(feature = "b1display")]
+    SetWidget(u8, Option<Widget>, bool),
+    /// Redraw a dashboard widget with new data. Optionally flush
+    #[cfg(feature = "b1display")]
+    UpdateWidget(u8, Vec<u8, WIDGET_DATA_BYTES>, bool),
     FlushFramebuffer,
     ClearRam,
     ScreenSaver(bool),
     GetScreenSaver,
+    /// Choose what the screensaver shows and when it starts
+    #[cfg(feature = "b1display")]
+    ScreenSaverMode(ScreenSaverConfig),
+    #[cfg(feature = "b1display")]
+    GetScreenSaverMode,
+    /// Write bitmap bytes for the screensaver: Size, offset, bytes
+    #[cfg(feature = "b1display")]
+    UploadBitmap(Size, usize, Vec<u8, BITMAP_CHUNK_BYTES>),
+    /// Start the screensaver when the content hasn't changed for a while
+    #[cfg(feature = "b1display")]
+    IdleProtection(IdleProtection),
+    #[cfg(feature = "b1display")]
+    GetIdleProtection,
+    /// Read back 32 bytes of the framebuffer copy, starting at the offset
+    #[cfg(feature = "b1display")]
+    GetFramebuffer(usize),
     SetFps(u8),
     GetFps,
     SetPowerMode(u8),
@@ -206,11 +352,42 @@ pub enum Command {
     SetAnimationPeriod(u16),
     /// Get the animation period in milliseconds
     GetAnimationPeriod,
+    /// Game step period in ms. 0 to let the game pick it again
+    #[cfg(feature = "ledmatrix")]
+    SetGameSpeed(u16),
+    #[cfg(feature = "ledmatrix")]
+    GetGameSpeed,
+    /// Best scores of the game, highest first
+    #[cfg(feature = "ledmatrix")]
+    GetHighScores(GameVal),
+    /// Clear the best scores of the game
+    #[cfg(feature = "ledmatrix")]
+    ResetHighScores(GameVal),
     #[cfg(feature = "ledmatrix")]
     SetPwmFreq(PwmFreqArg),
     GetPwmFreq,
     SetDebugMode(bool),
     GetDebugMode,
+    /// Set the time of day: hours, minutes, seconds
+    #[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+    SetTime(u8, u8, u8),
+    /// Show the clock with the given face, or stop showing it
+    #[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+    ClockMode(Option<ClockFace>),
+    #[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+    GetClockMode,
+    /// Stage rows of the strip to scroll through, starting at the offset
+    #[cfg(feature = "ledmatrix")]
+    StageScrollRows(usize, Vec<u16, SCROLL_ROWS_PER_COMMAND>),
+    /// Start scrolling through the staged strip
+    #[cfg(feature = "ledmatrix")]
+    StartScroll(ScrollParams),
+    /// Read back 30 LEDs of the grid, row by row, starting at the offset
+    #[cfg(feature = "ledmatrix")]
+    GetGrid(usize),
+    /// Show the back buffer on the next frame. Whether to respond, once it's shown
+    #[cfg(feature = "ledmatrix")]
+    Present(bool),
     _Unknown,
 }
 
@@ -228,31 +405,67 @@ pub struct C1MinimalState {
     pub brightness: u8,
 }
 
+#[cfg(feature = "b1display")]
 #[derive(Copy, Clone)]
 pub struct ScreenSaverState {
+    /// Usually the configured one, the idle protection can choose a different one
+    pub mode: ScreenSaverMode,
     pub rightwards: i32,
     pub downwards: i32,
+    /// Animation ticks since the screensaver started
+    pub ticks: u32,
+    /// Hours and minutes that the clock screensaver last drew
+    pub drawn_time: Option<(u8, u8)>,
 }
 
-impl Default for ScreenSaverState {
-    fn default() -> Self {
+#[cfg(feature = "b1display")]
+impl ScreenSaverState {
+    pub fn new(mode: ScreenSaverMode) -> Self {
         Self {
+            mode,
             rightwards: 1,
             downwards: 1,
+            ticks: 0,
+            drawn_time: None,
         }
     }
 }
 
+/// Pixels that fit into a single BlitRect command, 8 per byte
+/// 64B buffer minus 3B for magic and command, 8B for the rectangle and 1B for flags
+#[cfg(feature = "b1display")]
+pub const BLIT_BYTES: usize = 52;
+
+/// Bytes of UTF-8 text that fit into a single DrawText command
+/// 64B buffer minus 3B for magic and command, 8B for the layout and 1B for flags
+#[cfg(feature = "b1display")]
+pub const TEXT_BYTES: usize = 52;
+
 #[cfg(feature = "b1display")]
 pub struct B1DIsplayState {
     pub sleeping: SimpleSleepState,
     pub screen_inverted: bool,
     pub screen_on: bool,
     pub screensaver: Option<ScreenSaverState>,
+    pub screensaver_config: ScreenSaverConfig,
+    /// Timer ticks at which the screensaver starts, after booting or waking up
+    pub screensaver_due: Option<u64>,
+    pub idle_protection: IdleProtection,
+    /// Timer ticks when the content last changed, for the idle protection
+    pub idle_since: u64,
+    /// Bitmap for the screensaver, uploaded by the host
+    pub bitmap: Bitmap,
+    /// Copy of the content on the screen, without the screensaver
+    pub framebuffer: Framebuffer,
     pub power_mode: PowerMode,
     pub fps_config: FpsConfig,
     /// Animation period in microseconds
     pub animation_period: u64,
+    pub dashboard: Dashboard,
+    /// Time of day, once set by the host
+    pub time: Option<DeviceTime>,
+    /// Clock that's shown instead of anything else
+    pub clock: Option<ClockState>,
 }
 
 pub fn parse_command(count: usize, buf: &[u8]) -> Option<Command> {
@@ -277,6 +490,20 @@ pub fn parse_command(count: usize, buf: &[u8]) -> Option<Command> {
             Some(CommandVals::BootloaderReset) => Some(Command::BootloaderReset),
             Some(CommandVals::Panic) => Some(Command::Panic),
             Some(CommandVals::Version) => Some(Command::Version),
+            #[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+            Some(CommandVals::SetTime) => {
+                if count >= 6 && buf[3] < 24 && buf[4] < 60 && buf[5] < 60 {
+                    Some(Command::SetTime(buf[3], buf[4], buf[5]))
+                } else {
+                    None
+                }
+            }
+            #[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+            Some(CommandVals::ClockMode) => match arg {
+                Some(0) => Some(Command::ClockMode(None)),
+                Some(face) => Some(Command::ClockMode(Some(FromPrimitive::from_u8(face)?))),
+                None => Some(Command::GetClockMode),
+            },
             _ => None, //Some(Command::Unknown),
         }
     } else {
@@ -349,12 +576,21 @@ pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
             }
             Some(CommandVals::DrawGreyColBuffer) => Some(Command::DrawGreyColBuffer),
             Some(CommandVals::StartGame) => match arg.and_then(FromPrimitive::from_u8) {
-                Some(GameVal::Snake) => Some(Command::StartGame(Game::Snake)),
-                Some(GameVal::Pong) => Some(Command::StartGame(Game::Pong)),
-                Some(GameVal::Tetris) => None,
+                Some(GameVal::Snake) => Some(Command::StartGame(Game::Snake(SnakeStartParam {
+                    wrap: count >= 5 && buf[4] & 0x01 != 0,
+                    speed: if count >= 6 { buf[5] } else { 0 },
+                    speed_up: if count >= 7 { buf[6] } else { 0 },
+                    obstacles: if count >= 8 { buf[7] } else { 0 },
+                }))),
+                Some(GameVal::Pong) => Some(Command::StartGame(Game::Pong(PongStartParam {
+                    points_to_win: if count >= 5 { buf[4] } else { 0 },
+                    players: if count >= 6 { buf[5] } else { 1 },
+                }))),
+                Some(GameVal::Tetris) => Some(Command::StartGame(Game::Tetris)),
                 Some(GameVal::GameOfLife) => {
                     if count >= 5 {
                         FromPrimitive::from_u8(buf[4])
+                            .and_then(|pattern| parse_game_of_life(pattern, count, buf))
                             .map(|x| Command::StartGame(Game::GameOfLife(x)))
                     } else {
                         None
@@ -374,9 +610,29 @@ pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
                 Some(GameControlArg::SecondRight) => {
                     Some(Command::GameControl(GameControlArg::SecondRight))
                 }
+                Some(GameControlArg::Pause) => Some(Command::GameControl(GameControlArg::Pause)),
+                Some(GameControlArg::Resume) => Some(Command::GameControl(GameControlArg::Resume)),
+                Some(GameControlArg::Restart) => {
+                    Some(Command::GameControl(GameControlArg::Restart))
+                }
                 _ => None,
             },
             Some(CommandVals::GameStatus) => Some(Command::GameStatus),
+            Some(CommandVals::GameSpeed) => {
+                if count == 3 + 2 {
+                    let period = u16::from_le_bytes([buf[3], buf[4]]);
+                    Some(Command::SetGameSpeed(period))
+                } else {
+                    Some(Command::GetGameSpeed)
+                }
+            }
+            Some(CommandVals::HighScores) => match arg.and_then(FromPrimitive::from_u8) {
+                Some(game) if count >= 5 && buf[4] & 0x01 != 0 => {
+                    Some(Command::ResetHighScores(game))
+                }
+                Some(game) => Some(Command::GetHighScores(game)),
+                None => None,
+            },
             Some(CommandVals::AnimationPeriod) => {
                 if count == 3 + 2 {
                     let period = u16::from_le_bytes([buf[3], buf[4]]);
@@ -397,6 +653,48 @@ pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
             } else {
                 Command::GetDebugMode
             }),
+            Some(CommandVals::GetGrid) => {
+                //  3B for magic and command
+                //  2B for offset (u16)
+                if count >= 5 {
+                    let offset = u16::from_le_bytes([buf[3], buf[4]]);
+                    Some(Command::GetGrid(offset as usize))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::Present) => Some(Command::Present(
+                arg.map(|flags| flags & 0x01 != 0).unwrap_or(false),
+            )),
+            Some(CommandVals::StageScrollRows) => {
+                //  3B for magic and command
+                //  2B for offset (u16)
+                // 2B per row (u16)
+                if count >= 3 + 2 + 2 {
+                    let offset = u16::from_le_bytes([buf[3], buf[4]]);
+                    let mut rows = Vec::new();
+                    let row_count = ((count - 5) / 2).min(SCROLL_ROWS_PER_COMMAND);
+                    for i in 0..row_count {
+                        let _ = rows.push(u16::from_le_bytes([buf[5 + 2 * i], buf[6 + 2 * i]]));
+                    }
+                    Some(Command::StageScrollRows(offset as usize, rows))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::StartScroll) => {
+                if count >= 3 + 4 {
+                    let len = u16::from_le_bytes([buf[3], buf[4]]);
+                    Some(Command::StartScroll(ScrollParams {
+                        len: len as usize,
+                        frames_per_step: buf[5],
+                        looping: buf[6] & 0b01 != 0,
+                        from_top: buf[6] & 0b10 != 0,
+                    }))
+                } else {
+                    None
+                }
+            }
             _ => None,
         }
     } else {
@@ -404,6 +702,55 @@ pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
     }
 }
 
+/// Options after the start pattern. Everything but the seed bitmap is optional
+#[cfg(feature = "ledmatrix")]
+fn parse_game_of_life(
+    pattern: GameOfLifeStartParam,
+    count: usize,
+    buf: &[u8],
+) -> Option<GameOfLifeOptions> {
+    let flags = if count >= 6 { buf[5] } else { 0 };
+    let rules = if count >= 10 {
+        LifeRules {
+            birth: u16::from_le_bytes([buf[6], buf[7]]),
+            survive: u16::from_le_bytes([buf[8], buf[9]]),
+        }
+    } else {
+        LifeRules::default()
+    };
+    let mut seed = [0; SEED_BYTES];
+    if let GameOfLifeStartParam::Bitmap = pattern {
+        if count < 10 + SEED_BYTES {
+            return None;
+        }
+        seed.copy_from_slice(&buf[10..10 + SEED_BYTES]);
+    }
+
+    Some(GameOfLifeOptions {
+        pattern,
+        // Nothing would ever live, so it must've been left out
+        rules: if rules.birth == 0 && rules.survive == 0 {
+            LifeRules::default()
+        } else {
+            rules
+        },
+        wrap: flags & 0b001 == 0,
+        auto_restart: flags & 0b010 != 0,
+        show_age: flags & 0b100 != 0,
+        seed,
+    })
+}
+
+#[cfg(feature = "b1display")]
+fn parse_alignment(alignment: u8) -> Option<Alignment> {
+    match alignment {
+        0 => Some(Alignment::Left),
+        1 => Some(Alignment::Center),
+        2 => Some(Alignment::Right),
+        _ => None,
+    }
+}
+
 #[cfg(feature = "b1display")]
 pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
     if count >= 3 && buf[0] == 0x32 && buf[1] == 0xAC {
@@ -454,6 +801,155 @@ pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
                     None
                 }
             }
+            Some(CommandVals::BlitRect) => {
+                //  3B for magic and command
+                //  8B for x, y, width and height (u16 each)
+                //  1B for flags
+                // Up to 52B for pixels, 8 per byte
+                if count >= 12 {
+                    let x = u16::from_le_bytes([buf[3], buf[4]]);
+                    let y = u16::from_le_bytes([buf[5], buf[6]]);
+                    let width = u16::from_le_bytes([buf[7], buf[8]]);
+                    let height = u16::from_le_bytes([buf[9], buf[10]]);
+                    let flush = buf[11] & 0x01 != 0;
+
+                    let pixel_bytes = count - 12;
+                    if pixel_bytes > BLIT_BYTES
+                        || (width as usize * height as usize) > pixel_bytes * 8
+                    {
+                        return None;
+                    }
+                    let mut pixels = [0; BLIT_BYTES];
+                    pixels[..pixel_bytes].copy_from_slice(&buf[12..count]);
+
+                    let rect = Rectangle::new(
+                        Point::new(x as i32, y as i32),
+                        Size::new(width as u32, height as u32),
+                    );
+                    Some(Command::BlitRect(rect, pixels, flush))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::RleFramebuffer) => {
+                //  3B for magic and command
+                //  2B for offset (u16)
+                // Up to 59B of encoded data
+                if count >= 3 + 2 {
+                    let offset = u16::from_le_bytes([buf[3], buf[4]]);
+                    let len = count - 5;
+                    let mut data = [0; RLE_BYTES];
+                    data[..len].copy_from_slice(&buf[5..count]);
+                    Some(Command::RleFramebuffer(offset as usize, data, len))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::DrawText) => {
+                //  3B for magic and command
+                //  4B for x and y (u16 each)
+                //  1B for font
+                //  1B for alignment
+                //  2B for wrap width (u16)
+                //  1B for flags
+                // Up to 52B of UTF-8 text
+                if count >= 12 {
+                    let x = u16::from_le_bytes([buf[3], buf[4]]);
+                    let y = u16::from_le_bytes([buf[5], buf[6]]);
+                    let font = FromPrimitive::from_u8(buf[7])?;
+                    let alignment = parse_alignment(buf[8])?;
+                    let wrap_width = u16::from_le_bytes([buf[9], buf[10]]);
+                    let inverted = buf[11] & 0x01 != 0;
+                    let flush = buf[11] & 0x02 != 0;
+
+                    let text_str = core::str::from_utf8(&buf[12..count]).ok()?;
+                    let mut text: String<TEXT_BYTES> = String::new();
+                    text.push_str(text_str).ok()?;
+
+                    let layout = TextLayout {
+                        position: Point::new(x as i32, y as i32),
+                        font,
+                        alignment,
+                        wrap_width: wrap_width as u32,
+                        inverted,
+                    };
+                    Some(Command::DrawText(layout, text, flush))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::ClearRegion) => {
+                //  3B for magic and command
+                //  8B for x, y, width and height (u16 each)
+                //  1B for flags
+                if count >= 12 {
+                    let x = u16::from_le_bytes([buf[3], buf[4]]);
+                    let y = u16::from_le_bytes([buf[5], buf[6]]);
+                    let width = u16::from_le_bytes([buf[7], buf[8]]);
+                    let height = u16::from_le_bytes([buf[9], buf[10]]);
+                    let black = buf[11] & 0x01 != 0;
+                    let flush = buf[11] & 0x02 != 0;
+
+                    let rect = Rectangle::new(
+                        Point::new(x as i32, y as i32),
+                        Size::new(width as u32, height as u32),
+                    );
+                    Some(Command::ClearRegion(rect, black, flush))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::SetWidget) => {
+                //  3B for magic and command
+                //  1B for ID
+                //  1B for kind, 0 to remove
+                //  8B for x, y, width and height (u16 each)
+                //  1B for font
+                //  1B for alignment
+                //  1B for flags
+                if count >= 16 {
+                    let id = buf[3];
+                    let x = u16::from_le_bytes([buf[5], buf[6]]);
+                    let y = u16::from_le_bytes([buf[7], buf[8]]);
+                    let width = u16::from_le_bytes([buf[9], buf[10]]);
+                    let height = u16::from_le_bytes([buf[11], buf[12]]);
+                    let flush = buf[15] & 0x01 != 0;
+
+                    let widget = if buf[4] == 0 {
+                        None
+                    } else {
+                        Some(Widget {
+                            kind: FromPrimitive::from_u8(buf[4])?,
+                            area: Rectangle::new(
+                                Point::new(x as i32, y as i32),
+                                Size::new(width as u32, height as u32),
+                            ),
+                            font: FromPrimitive::from_u8(buf[13])?,
+                            alignment: parse_alignment(buf[14])?,
+                        })
+                    };
+                    Some(Command::SetWidget(id, widget, flush))
+                } else if count >= 5 && buf[4] == 0 {
+                    // Removing only needs the ID
+                    Some(Command::SetWidget(buf[3], None, false))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::UpdateWidget) => {
+                //  3B for magic and command
+                //  1B for ID
+                //  1B for flags
+                // Up to 59B of data, depending on the kind of widget
+                if count >= 5 {
+                    let id = buf[3];
+                    let flush = buf[4] & 0x01 != 0;
+                    let data = Vec::from_slice(&buf[5..count]).ok()?;
+                    Some(Command::UpdateWidget(id, data, flush))
+                } else {
+                    None
+                }
+            }
             Some(CommandVals::FlushFramebuffer) => Some(Command::FlushFramebuffer),
             Some(CommandVals::ClearRam) => Some(Command::ClearRam),
             Some(CommandVals::ScreenSaver) => Some(if let Some(on) = arg {
@@ -461,6 +957,63 @@ pub fn parse_module_command(count: usize, buf: &[u8]) -> Option<Command> {
             } else {
                 Command::GetScreenSaver
             }),
+            Some(CommandVals::ScreenSaverMode) => {
+                //  3B for magic and command
+                //  1B for mode
+                //  2B for timeout in seconds (u16)
+                //  1B for flags
+                if count >= 7 {
+                    Some(Command::ScreenSaverMode(ScreenSaverConfig {
+                        mode: FromPrimitive::from_u8(buf[3])?,
+                        timeout: u16::from_le_bytes([buf[4], buf[5]]),
+                        on_wake: buf[6] & 0x01 != 0,
+                    }))
+                } else {
+                    Some(Command::GetScreenSaverMode)
+                }
+            }
+            Some(CommandVals::IdleProtection) => {
+                //  3B for magic and command
+                //  2B for timeout in seconds (u16)
+                //  1B for mode
+                if count >= 6 {
+                    Some(Command::IdleProtection(IdleProtection {
+                        timeout: u16::from_le_bytes([buf[3], buf[4]]),
+                        mode: FromPrimitive::from_u8(buf[5])?,
+                    }))
+                } else {
+                    Some(Command::GetIdleProtection)
+                }
+            }
+            Some(CommandVals::GetFramebuffer) => {
+                //  3B for magic and command
+                //  2B for offset (u16)
+                if count >= 5 {
+                    let offset = u16::from_le_bytes([buf[3], buf[4]]);
+                    Some(Command::GetFramebuffer(offset as usize))
+                } else {
+                    None
+                }
+            }
+            Some(CommandVals::UploadBitmap) => {
+                //  3B for magic and command
+                //  4B for width and height (u16 each)
+                //  2B for offset (u16)
+                // Up to 55B for pixels, 8 per byte
+                if count >= 9 {
+                    let width = u16::from_le_bytes([buf[3], buf[4]]);
+                    let height = u16::from_le_bytes([buf[5], buf[6]]);
+                    let offset = u16::from_le_bytes([buf[7], buf[8]]);
+                    let size = Size::new(width as u32, height as u32);
+                    if !Bitmap::fits(size) {
+                        return None;
+                    }
+                    let bytes = Vec::from_slice(&buf[9..count]).ok()?;
+                    Some(Command::UploadBitmap(size, offset as usize, bytes))
+                } else {
+                    None
+                }
+            }
             Some(CommandVals::SetFps) => Some(if let Some(fps) = arg {
                 Command::SetFps(fps)
             } else {
@@ -520,6 +1073,20 @@ pub fn handle_command(
 ) -> Option<[u8; 32]> {
     use crate::games::game_of_life;
 
+    // Showing something else stops the clock
+    if matches!(
+        command,
+        Command::Percentage(_)
+            | Command::Pattern(_)
+            | Command::Draw(_)
+            | Command::DrawGreyColBuffer
+            | Command::Present(_)
+            | Command::StartGame(_)
+            | Command::StartScroll(_)
+    ) {
+        state.clock = None;
+    }
+
     match command {
         Command::GetBrightness => {
             let mut response: [u8; 32] = [0; 32];
@@ -555,6 +1122,9 @@ pub fn handle_command(
         }
         Command::SetAnimate(a) => {
             state.animate = *a;
+            if !state.animate {
+                state.scroll_offset = 0;
+            }
             None
         }
         Command::GetAnimate => {
@@ -567,14 +1137,19 @@ pub fn handle_command(
             None
         }
         Command::StageGreyCol(col, vals) => {
-            draw_grey_col(&mut state.col_buffer, *col, vals);
+            draw_grey_col(&mut state.back_buffer, *col, vals);
             None
         }
         Command::DrawGreyColBuffer => {
             // Copy the staging buffer to the real grid and display it
-            state.grid = state.col_buffer.clone();
+            state.grid = state.back_buffer.clone();
             // Zero the old staging buffer, just for good measure.
-            state.col_buffer = percentage(0);
+            state.back_buffer = percentage(0);
+            None
+        }
+        Command::Present(respond) => {
+            // Swapped in by the main loop, right before the next frame is rendered
+            state.present_pending = Some(*respond);
             None
         }
         // TODO: Move to handle_generic_command
@@ -587,24 +1162,53 @@ pub fn handle_command(
             Some(response)
         }
         Command::StartGame(game) => {
+            state.game_paused = false;
+            state.game_paused_by_sleep = false;
+            state.game_step_period = None;
+            state.new_high_score = None;
             match game {
-                Game::Snake => snake::start_game(state, random),
-                Game::Pong => pong::start_game(state, random),
-                Game::Tetris => {}
-                Game::GameOfLife(param) => game_of_life::start_game(state, random, *param),
+                Game::Snake(param) => snake::start_game(state, random, *param),
+                Game::Pong(param) => pong::start_game(state, random, *param),
+                Game::Tetris => tetris::start_game(state, random),
+                Game::GameOfLife(options) => game_of_life::start_game(state, random, *options),
             }
             None
         }
         Command::GameControl(arg) => {
-            match state.game {
-                Some(GameState::Snake(_)) => snake::handle_control(state, arg),
-                Some(GameState::Pong(_)) => pong::handle_control(state, arg),
-                Some(GameState::GameOfLife(_)) => game_of_life::handle_control(state, arg),
-                _ => {}
+            games::handle_control(state, arg, random);
+            None
+        }
+        Command::GameStatus => Some(games::game_status(state)),
+        Command::SetGameSpeed(period) => {
+            state.game_step_period = match period {
+                0 => None,
+                ms => Some(*ms as u64 * 1_000),
+            };
+            None
+        }
+        Command::GetGameSpeed => {
+            let mut response: [u8; 32] = [0; 32];
+            if state.game.is_some() {
+                let period_ms = games::step_period(state) / 1_000;
+                response[0..2].copy_from_slice(&(period_ms as u16).to_le_bytes());
+                response[2] = state.game_step_period.is_some() as u8;
+            }
+            Some(response)
+        }
+        Command::GetHighScores(game) => {
+            let mut response: [u8; 32] = [0; 32];
+            if let Some(table) = state.high_scores.table(*game) {
+                for (i, score) in table.iter().enumerate() {
+                    response[i * 4..i * 4 + 4].copy_from_slice(&score.to_le_bytes());
+                }
             }
+            Some(response)
+        }
+        Command::ResetHighScores(game) => {
+            state.high_scores.reset(*game);
+            state.high_scores.save();
             None
         }
-        Command::GameStatus => None,
         Command::SetAnimationPeriod(period) => {
             state.animation_period = (*period as u64) * 1_000;
             None
@@ -635,10 +1239,97 @@ pub fn handle_command(
             response[0] = state.debug_mode as u8;
             Some(response)
         }
+        Command::GetGrid(offset) => {
+            let mut response: [u8; 32] = [0; 32];
+            response[0] = (state.animate as u8)
+                | (state.game.is_some() as u8) << 1
+                | (state.upcoming_frames.is_some() as u8) << 2
+                | (state.clock.is_some() as u8) << 3;
+            response[1] = state.brightness;
+            for (i, value) in response[2..].iter_mut().enumerate() {
+                let led = offset + i;
+                if led < LEDS {
+                    // The columns of the grid are stored from the right
+                    *value = state.shown_pixel(WIDTH - 1 - led % WIDTH, led / WIDTH);
+                }
+            }
+            Some(response)
+        }
+        Command::StageScrollRows(offset, rows) => {
+            state.scroll_strip.stage(*offset, rows);
+            None
+        }
+        Command::StartScroll(params) => {
+            state.upcoming_frames = if params.len == 0 {
+                None
+            } else {
+                Some(Animation::Scroll(ScrollIterator::new(
+                    state.scroll_strip.clone(),
+                    *params,
+                )))
+            };
+            None
+        }
+        // Needs the timer, so it's handled in the main loop
+        Command::SetTime(_, _, _) => None,
+        Command::ClockMode(face) => {
+            state.game = None;
+            state.clock = face.map(|face| ClockState { face, drawn: None });
+            None
+        }
+        Command::GetClockMode => {
+            let mut response: [u8; 32] = [0; 32];
+            response[0] = state.clock.map_or(0, |clock| clock.face as u8);
+            Some(response)
+        }
         _ => handle_generic_command(command),
     }
 }
 
+/// Turn screensaver and clock off, when drawing something
+/// What the screensaver drew over is restored. What the clock drew stays.
+#[cfg(feature = "b1display")]
+fn stop_screensaver<SPI, DC, RST, const COLS: usize, const ROWS: usize>(
+    state: &mut B1DIsplayState,
+    disp: &mut ST7306<SPI, DC, RST, COLS, ROWS>,
+) where
+    SPI: SpiDevice,
+    DC: OutputPin,
+    RST: OutputPin,
+{
+    state.clock = None;
+    state.screensaver_due = None;
+    if let Some(screensaver) = state.screensaver.take() {
+        if screensaver.mode == ScreenSaverMode::Invert {
+            disp.invert_screen(state.screen_inverted).unwrap();
+        }
+        disp.draw_pixels(state.framebuffer.shifted(Point::zero()), false)
+            .unwrap();
+    }
+}
+
+#[cfg(feature = "b1display")]
+impl Command {
+    /// Whether the host shows something new or takes care of the screensaver
+    /// Keeps the idle protection from starting the screensaver for a while.
+    pub fn is_activity(&self) -> bool {
+        matches!(
+            self,
+            Command::SetText(_)
+                | Command::SetPixelColumn(_, _)
+                | Command::BlitRect(_, _, _)
+                | Command::RleFramebuffer(_, _, _)
+                | Command::DrawText(_, _, _)
+                | Command::ClearRegion(_, _, _)
+                | Command::SetWidget(_, _, _)
+                | Command::UpdateWidget(_, _, _)
+                | Command::ClearRam
+                | Command::ScreenSaver(_)
+                | Command::ClockMode(_)
+        )
+    }
+}
+
 #[cfg(feature = "b1display")]
 pub fn handle_command<SPI, DC, RST, DELAY, const COLS: usize, const ROWS: usize>(
     command: &Command,
@@ -665,18 +1356,18 @@ where
         }
         Command::Panic => panic!("Ahhh"),
         Command::SetText(text) => {
-            // Turn screensaver off, when drawing something
-            state.screensaver = None;
+            stop_screensaver(state, disp);
 
+            let mut target = Mirrored::new(disp, &mut state.framebuffer);
             clear_text(
-                disp,
+                &mut target,
                 Point::new(LOGO_OFFSET_X, LOGO_OFFSET_Y + logo_rect.size.height as i32),
                 Rgb565::WHITE,
             )
             .unwrap();
 
             draw_text(
-                disp,
+                &mut target,
                 text,
                 Point::new(LOGO_OFFSET_X, LOGO_OFFSET_Y + logo_rect.size.height as i32),
             )
@@ -705,8 +1396,7 @@ where
             Some(response)
         }
         Command::SetPixelColumn(column, pixel_bytes) => {
-            // Turn screensaver off, when drawing something
-            state.screensaver = None;
+            stop_screensaver(state, disp);
 
             let mut pixels: [bool; 400] = [false; 400];
             for (i, byte) in pixel_bytes.iter().enumerate() {
@@ -720,35 +1410,165 @@ where
                 pixels[8 * i + 7] = byte & 0b10000000 != 0;
             }
             disp.draw_pixels(
-                pixels.iter().enumerate().map(|(y, black)| {
+                pixels
+                    .iter()
+                    .enumerate()
+                    .map(|(y, black)| {
+                        Pixel(
+                            Point::new(*column as i32, y as i32),
+                            if *black { Rgb565::BLACK } else { Rgb565::WHITE },
+                        )
+                    })
+                    .inspect(|pixel| state.framebuffer.set_pixel(*pixel)),
+                false,
+            )
+            .unwrap();
+            None
+        }
+        Command::BlitRect(rect, pixel_bytes, flush) => {
+            stop_screensaver(state, disp);
+
+            let screen = disp.bounding_box();
+            disp.draw_pixels(
+                rect.points()
+                    .enumerate()
+                    .filter(|(_, point)| screen.contains(*point))
+                    .map(|(i, point)| {
+                        let black = pixel_bytes[i / 8] & (1 << (i % 8)) != 0;
+                        Pixel(point, if black { Rgb565::BLACK } else { Rgb565::WHITE })
+                    })
+                    .inspect(|pixel| state.framebuffer.set_pixel(*pixel)),
+                false,
+            )
+            .unwrap();
+            // TODO: Only flush the window of the rectangle. The st7306 driver owns the
+            // SPI bus and only writes its whole RAM buffer, so it needs an API to set
+            // the column and row address to a window and write just those bytes.
+            if *flush {
+                disp.flush().unwrap();
+            }
+            None
+        }
+        Command::RleFramebuffer(offset, data, len) => {
+            stop_screensaver(state, disp);
+
+            let screen = disp.bounding_box();
+            let pixels = RleDecoder::new(&data[..*len], *offset).flat_map(|(pos, byte)| {
+                let x = (pos / COLUMN_BYTES) as i32;
+                let y = ((pos % COLUMN_BYTES) * 8) as i32;
+                (0..8).map(move |bit| {
+                    let black = byte & (1 << bit) != 0;
                     Pixel(
-                        Point::new(*column as i32, y as i32),
-                        if *black { Rgb565::BLACK } else { Rgb565::WHITE },
+                        Point::new(x, y + bit),
+                        if black { Rgb565::BLACK } else { Rgb565::WHITE },
                     )
-                }),
+                })
+            });
+            disp.draw_pixels(
+                pixels
+                    .filter(|Pixel(point, _)| screen.contains(*point))
+                    .inspect(|pixel| state.framebuffer.set_pixel(*pixel)),
                 false,
             )
             .unwrap();
             None
         }
+        Command::DrawText(layout, text, flush) => {
+            stop_screensaver(state, disp);
+
+            let mut target = Mirrored::new(disp, &mut state.framebuffer);
+            draw_text_layout(&mut target, text, layout).unwrap();
+            if *flush {
+                disp.flush().unwrap();
+            }
+            None
+        }
+        Command::ClearRegion(rect, black, flush) => {
+            stop_screensaver(state, disp);
+
+            let color = if *black { Rgb565::BLACK } else { Rgb565::WHITE };
+            clear_region(
+                &mut Mirrored::new(disp, &mut state.framebuffer),
+                *rect,
+                color,
+            )
+            .unwrap();
+            if *flush {
+                disp.flush().unwrap();
+            }
+            None
+        }
+        Command::SetWidget(id, widget, flush) => {
+            stop_screensaver(state, disp);
+
+            if *id == 0xFF && widget.is_none() {
+                state.dashboard.clear();
+            } else {
+                let previous = state.dashboard.set(*id as usize, *widget);
+                let mut target = Mirrored::new(disp, &mut state.framebuffer);
+                // Remove what the widget was showing before
+                if let Some(previous) = previous {
+                    clear_region(&mut target, previous.area, Rgb565::WHITE).unwrap();
+                }
+                if let Some(widget) = widget {
+                    draw_widget(&mut target, widget, &[]).unwrap();
+                }
+            }
+            if *flush {
+                disp.flush().unwrap();
+            }
+            None
+        }
+        Command::UpdateWidget(id, data, flush) => {
+            if let Some(widget) = state.dashboard.get(*id as usize).copied() {
+                stop_screensaver(state, disp);
+
+                let mut target = Mirrored::new(disp, &mut state.framebuffer);
+                draw_widget(&mut target, &widget, data).unwrap();
+                if *flush {
+                    disp.flush().unwrap();
+                }
+            }
+            None
+        }
         Command::FlushFramebuffer => {
             disp.flush().unwrap();
             None
         }
         Command::ClearRam => {
-            // Turn screensaver off, when drawing something
-            state.screensaver = None;
+            stop_screensaver(state, disp);
 
             disp.clear_ram().unwrap();
+            state.framebuffer.clear();
             None
         }
+        // Needs the timer, so it's handled in the main loop
+        Command::SetTime(_, _, _) => None,
+        Command::ClockMode(face) => {
+            // The clock redraws the whole screen, nothing to restore
+            if face.is_some() {
+                state.screensaver = None;
+                state.screensaver_due = None;
+            }
+            state.clock = face.map(|face| ClockState { face, drawn: None });
+            None
+        }
+        Command::GetClockMode => {
+            let mut response: [u8; 32] = [0; 32];
+            response[0] = state.clock.map_or(0, |clock| clock.face as u8);
+            Some(response)
+        }
         Command::ScreenSaver(on) => {
-            state.screensaver = match (*on, state.screensaver) {
-                (true, Some(x)) => Some(x),
-                (true, None) => Some(ScreenSaverState::default()),
-                (false, Some(_)) => None,
-                (false, None) => None,
-            };
+            state.screensaver_due = None;
+            if *on {
+                if state.screensaver.is_none() {
+                    state.screensaver = Some(ScreenSaverState::new(state.screensaver_config.mode));
+                }
+                state.clock = None;
+            } else if state.screensaver.is_some() {
+                stop_screensaver(state, disp);
+                disp.flush().unwrap();
+            }
             None
         }
         Command::GetScreenSaver => {
@@ -756,6 +1576,45 @@ where
             response[0] = state.screensaver.is_some() as u8;
             Some(response)
         }
+        Command::ScreenSaverMode(config) => {
+            state.screensaver_config = *config;
+            // Start over in the new mode
+            if state.screensaver.is_some() {
+                stop_screensaver(state, disp);
+                state.screensaver = Some(ScreenSaverState::new(config.mode));
+            }
+            None
+        }
+        Command::GetScreenSaverMode => {
+            let config = state.screensaver_config;
+            let mut response: [u8; 32] = [0; 32];
+            response[0] = config.mode as u8;
+            response[1..3].copy_from_slice(&config.timeout.to_le_bytes());
+            response[3] = config.on_wake as u8;
+            Some(response)
+        }
+        Command::IdleProtection(idle_protection) => {
+            state.idle_protection = *idle_protection;
+            None
+        }
+        Command::GetIdleProtection => {
+            let idle_protection = state.idle_protection;
+            let mut response: [u8; 32] = [0; 32];
+            response[0..2].copy_from_slice(&idle_protection.timeout.to_le_bytes());
+            response[2] = idle_protection.mode as u8;
+            Some(response)
+        }
+        Command::GetFramebuffer(offset) => {
+            let mut response: [u8; 32] = [0; 32];
+            let bytes = state.framebuffer.bytes().get(*offset..).unwrap_or(&[]);
+            let len = bytes.len().min(response.len());
+            response[..len].copy_from_slice(&bytes[..len]);
+            Some(response)
+        }
+        Command::UploadBitmap(size, offset, bytes) => {
+            state.bitmap.write(*size, *offset, bytes);
+            None
+        }
         Command::SetFps(fps) => {
             if let Some(fps_config) = FpsConfig::from_u8(*fps) {
                 state.fps_config = fps_config;
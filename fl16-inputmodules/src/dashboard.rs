@@ -0,0 +1,221 @@
+//! Dashboard of widgets on the B1 display
+//!
+//! The host defines where each widget goes and only sends the data to show
+//! in it. When the data changes, only that widget is redrawn.
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, Triangle};
+use embedded_graphics::text::Alignment;
+use heapless::String;
+
+use crate::graphics::{clear_region, draw_text_layout, TextFont, TextLayout};
+
+/// How many widgets can be on the dashboard at the same time
+pub const MAX_WIDGETS: usize = 16;
+/// Data of an UpdateWidget command
+/// 64B buffer minus 3B for magic and command, 1B for the ID and 1B for flags
+pub const WIDGET_DATA_BYTES: usize = 59;
+
+#[derive(Copy, Clone, num_derive::FromPrimitive)]
+pub enum WidgetKind {
+    /// UTF-8 text, wrapped to the width of the widget
+    Text = 0x01,
+    /// Hours, minutes and optionally seconds
+    Clock = 0x02,
+    /// Horizontal bar, filled by a percentage
+    Bar = 0x03,
+    /// Battery with a percentage and whether it's charging
+    Battery = 0x04,
+    /// Black and white pixels, row by row, 8 per byte
+    Icon = 0x05,
+}
+
+#[derive(Copy, Clone)]
+pub struct Widget {
+    pub kind: WidgetKind,
+    pub area: Rectangle,
+    /// Font of text and clock widgets
+    pub font: TextFont,
+    /// Alignment of text and clock widgets
+    pub alignment: Alignment,
+}
+
+#[derive(Default)]
+pub struct Dashboard {
+    widgets: [Option<Widget>; MAX_WIDGETS],
+}
+
+impl Dashboard {
+    pub fn get(&self, id: usize) -> Option<&Widget> {
+        self.widgets.get(id)?.as_ref()
+    }
+
+    /// Add, replace or, with None, remove a widget
+    /// Returns the widget that was there before
+    pub fn set(&mut self, id: usize, widget: Option<Widget>) -> Option<Widget> {
+        let slot = self.widgets.get_mut(id)?;
+        core::mem::replace(slot, widget)
+    }
+
+    /// Remove all widgets
+    pub fn clear(&mut self) {
+        self.widgets = [None; MAX_WIDGETS];
+    }
+}
+
+/// Redraw a widget with new data
+/// Nothing is drawn outside of its area. Missing data is drawn as empty.
+pub fn draw_widget<D>(target: &mut D, widget: &Widget, data: &[u8]) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let area = widget.area;
+    let mut target = target.clipped(&area);
+    clear_region(&mut target, area, Rgb565::WHITE)?;
+
+    match widget.kind {
+        WidgetKind::Text => {
+            let text = core::str::from_utf8(data).unwrap_or("");
+            draw_text_layout(&mut target, text, &widget_text_layout(widget, false))?;
+        }
+        WidgetKind::Clock => {
+            let mut text: String<16> = String::new();
+            match data {
+                [hours, minutes, seconds, ..] => core::fmt::write(
+                    &mut text,
+                    format_args!("{hours:02}:{minutes:02}:{seconds:02}"),
+                ),
+                [hours, minutes] => {
+                    core::fmt::write(&mut text, format_args!("{hours:02}:{minutes:02}"))
+                }
+                _ => core::fmt::write(&mut text, format_args!("--:--")),
+            }
+            .unwrap();
+            draw_text_layout(&mut target, &text, &widget_text_layout(widget, true))?;
+        }
+        WidgetKind::Bar => {
+            let percent = data.first().copied().unwrap_or(0);
+            area.into_styled(PrimitiveStyle::with_stroke(Rgb565::BLACK, 1))
+                .draw(&mut target)?;
+            // Leave a white gap between the outline and the fill
+            let inner = area.offset(-2);
+            let fill = Rectangle::new(
+                inner.top_left,
+                Size::new(percentage_of(inner.size.width, percent), inner.size.height),
+            );
+            fill.into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+                .draw(&mut target)?;
+        }
+        WidgetKind::Battery => {
+            let percent = data.first().copied().unwrap_or(0);
+            let charging = data.get(1).is_some_and(|flags| flags & 0x01 != 0);
+            draw_battery(&mut target, area, percent, charging)?;
+        }
+        WidgetKind::Icon => {
+            let pixels = area.points().enumerate().map(|(i, point)| {
+                let black = data
+                    .get(i / 8)
+                    .is_some_and(|byte| byte & (1 << (i % 8)) != 0);
+                Pixel(point, if black { Rgb565::BLACK } else { Rgb565::WHITE })
+            });
+            target.draw_iter(pixels)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Layout of the text in a text or clock widget
+/// Clocks are vertically centered, text starts at the top.
+fn widget_text_layout(widget: &Widget, center_vertically: bool) -> TextLayout {
+    let area = widget.area;
+    let x = match widget.alignment {
+        Alignment::Left => area.top_left.x,
+        Alignment::Center => area.center().x,
+        Alignment::Right => area.bottom_right().unwrap_or(area.top_left).x,
+    };
+    let y = if center_vertically {
+        let text_height = widget.font.mono_font().character_size.height;
+        area.top_left.y + (area.size.height.saturating_sub(text_height) / 2) as i32
+    } else {
+        area.top_left.y
+    };
+
+    TextLayout {
+        position: Point::new(x, y),
+        font: widget.font,
+        alignment: widget.alignment,
+        wrap_width: area.size.width,
+        inverted: false,
+    }
+}
+
+fn percentage_of(value: u32, percent: u8) -> u32 {
+    value * percent.min(100) as u32 / 100
+}
+
+/// Draw a battery, with the terminal on the right side
+fn draw_battery<D>(
+    target: &mut D,
+    area: Rectangle,
+    percent: u8,
+    charging: bool,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let terminal_width = (area.size.width / 10).max(2);
+    let body = Rectangle::new(
+        area.top_left,
+        Size::new(
+            area.size.width.saturating_sub(terminal_width),
+            area.size.height,
+        ),
+    );
+    let terminal = Rectangle::new(
+        area.top_left + Point::new(body.size.width as i32, (area.size.height / 4) as i32),
+        Size::new(terminal_width, area.size.height / 2),
+    );
+
+    body.into_styled(PrimitiveStyle::with_stroke(Rgb565::BLACK, 2))
+        .draw(target)?;
+    terminal
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+        .draw(target)?;
+
+    let inner = body.offset(-4);
+    Rectangle::new(
+        inner.top_left,
+        Size::new(percentage_of(inner.size.width, percent), inner.size.height),
+    )
+    .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+    .draw(target)?;
+
+    if charging {
+        // Lightning bolt, outlined in white to be visible on the fill as well
+        let center = body.center();
+        let w = (inner.size.width / 6) as i32;
+        let h = (inner.size.height / 2) as i32;
+        let style = PrimitiveStyleBuilder::new()
+            .fill_color(Rgb565::BLACK)
+            .stroke_color(Rgb565::WHITE)
+            .stroke_width(1)
+            .build();
+        Triangle::new(
+            center + Point::new(w / 2, -h),
+            center + Point::new(-w, h / 4),
+            center + Point::new(w / 4, h / 4),
+        )
+        .into_styled(style)
+        .draw(target)?;
+        Triangle::new(
+            center + Point::new(-w / 2, h),
+            center + Point::new(w, -h / 4),
+            center + Point::new(-w / 4, -h / 4),
+        )
+        .into_styled(style)
+        .draw(target)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Persistent storage in the flash, next to the firmware
+//!
+//! The flash is mapped into memory, so reading is just reading from there.
+//! Writing needs the flash to leave XIP mode, so no code can run from it in the
+//! meantime. The ROM functions do the work and are called from a function in
+//! RAM, with interrupts disabled. Afterwards boot2 puts the flash back into the
+//! fast XIP mode it was in. See flash_layout.md for where things are.
+use rp2040_hal::rom_data;
+
+/// Where the flash is mapped into memory
+const FLASH_OFFSET: usize = 0x10000000;
+/// Smallest unit that can be erased
+pub const SECTOR_SIZE: usize = 0x1000;
+/// Smallest unit that can be programmed
+pub const PAGE_SIZE: usize = 0x100;
+/// Persistent storage for the LED Matrix, right before the serial number
+pub const PERSISTENT_STORAGE: usize = 0xFE000;
+
+/// Erase a whole block at once, if possible
+const BLOCK_SIZE: u32 = 0x10000;
+const BLOCK_ERASE_CMD: u8 = 0xD8;
+
+/// Copy of boot2, which sets up XIP, from the start of the flash
+static mut BOOT2: [u32; 64] = [0; 64];
+
+/// Read a page of the flash
+pub fn read_page(offset: usize) -> &'static [u8; PAGE_SIZE] {
+    let ptr = (FLASH_OFFSET + offset) as *const [u8; PAGE_SIZE];
+    unsafe { &*ptr }
+}
+
+/// Replace the sector at the offset with a single page of data, the rest is erased
+pub fn write_page(offset: usize, data: &[u8; PAGE_SIZE]) {
+    // Look the functions up while the flash is still there to run the lookup from
+    let functions = FlashFunctions {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        flash_range_erase: rom_data::flash_range_erase::ptr(),
+        flash_range_program: rom_data::flash_range_program::ptr(),
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+    };
+    // The data must not be in the flash either
+    let data = *data;
+    cortex_m::interrupt::free(|_| unsafe {
+        let boot2 = &raw mut BOOT2;
+        core::ptr::copy_nonoverlapping(FLASH_OFFSET as *const u32, boot2.cast::<u32>(), 64);
+        erase_and_program(&functions, boot2 as usize + 1, offset as u32, &data);
+    });
+}
+
+struct FlashFunctions {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+}
+
+/// Runs from RAM, while the flash isn't available
+/// boot2 is the address of the copy of boot2, with the thumb bit set
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn erase_and_program(
+    functions: &FlashFunctions,
+    boot2: usize,
+    offset: u32,
+    data: &[u8; PAGE_SIZE],
+) {
+    (functions.connect_internal_flash)();
+    (functions.flash_exit_xip)();
+    (functions.flash_range_erase)(offset, SECTOR_SIZE, BLOCK_SIZE, BLOCK_ERASE_CMD);
+    (functions.flash_range_program)(offset, data.as_ptr(), PAGE_SIZE);
+    (functions.flash_flush_cache)();
+    let enter_xip: unsafe extern "C" fn() = core::mem::transmute(boot2);
+    enter_xip();
+}
@@ -0,0 +1,149 @@
+//! Copy of the content that's shown on the B1 display
+//!
+//! The display driver can't read back what it shows, so everything that's drawn
+//! as content is mirrored into this framebuffer. That way it can be drawn again,
+//! after the screensaver drew over it, or shifted by a few pixels.
+//!
+//! The layout is the same that SetPixelColumn and RleFramebuffer use: Column by
+//! column, 8 pixels per byte from top to bottom. A set bit is black.
+use core::convert::Infallible;
+
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+use crate::rle::COLUMN_BYTES;
+
+pub const WIDTH: usize = 300;
+pub const HEIGHT: usize = 400;
+pub const FRAMEBUFFER_BYTES: usize = WIDTH * COLUMN_BYTES;
+
+pub struct Framebuffer {
+    bytes: [u8; FRAMEBUFFER_BYTES],
+}
+
+impl Default for Framebuffer {
+    /// All white
+    fn default() -> Self {
+        Framebuffer {
+            bytes: [0; FRAMEBUFFER_BYTES],
+        }
+    }
+}
+
+/// The display only shows black and white, dark colors become black
+pub fn is_black(color: Rgb565) -> bool {
+    color.g() < 32
+}
+
+impl Framebuffer {
+    /// Bytes in the same layout as SetPixelColumn and RleFramebuffer
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Make everything white
+    pub fn clear(&mut self) {
+        self.bytes = [0; FRAMEBUFFER_BYTES];
+    }
+
+    /// Position of the byte and bit of a pixel
+    fn index(point: Point) -> Option<(usize, u8)> {
+        let (x, y) = (
+            usize::try_from(point.x).ok()?,
+            usize::try_from(point.y).ok()?,
+        );
+        if x >= WIDTH || y >= HEIGHT {
+            return None;
+        }
+        Some((x * COLUMN_BYTES + y / 8, 1 << (y % 8)))
+    }
+
+    pub fn set_pixel(&mut self, Pixel(point, color): Pixel<Rgb565>) {
+        if let Some((i, bit)) = Self::index(point) {
+            if is_black(color) {
+                self.bytes[i] |= bit;
+            } else {
+                self.bytes[i] &= !bit;
+            }
+        }
+    }
+
+    /// Pixels outside of the screen are white
+    pub fn pixel(&self, point: Point) -> Rgb565 {
+        match Self::index(point) {
+            Some((i, bit)) if self.bytes[i] & bit != 0 => Rgb565::BLACK,
+            _ => Rgb565::WHITE,
+        }
+    }
+
+    /// All pixels of the screen, with the content moved by the offset
+    /// What's moved off the screen is dropped, what's uncovered is white.
+    pub fn shifted(&self, offset: Point) -> impl Iterator<Item = Pixel<Rgb565>> + '_ {
+        self.bounding_box()
+            .points()
+            .map(move |point| Pixel(point, self.pixel(point - offset)))
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for pixel in pixels {
+            self.set_pixel(pixel);
+        }
+        Ok(())
+    }
+}
+
+/// Draws onto the display and mirrors everything into the framebuffer
+pub struct Mirrored<'a, D> {
+    pub display: &'a mut D,
+    pub framebuffer: &'a mut Framebuffer,
+}
+
+impl<'a, D> Mirrored<'a, D> {
+    pub fn new(display: &'a mut D, framebuffer: &'a mut Framebuffer) -> Self {
+        Mirrored {
+            display,
+            framebuffer,
+        }
+    }
+}
+
+impl<D: Dimensions> Dimensions for Mirrored<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.display.bounding_box()
+    }
+}
+
+impl<D> DrawTarget for Mirrored<'_, D>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    type Color = Rgb565;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let framebuffer = &mut *self.framebuffer;
+        self.display.draw_iter(
+            pixels
+                .into_iter()
+                .inspect(|pixel| framebuffer.set_pixel(*pixel)),
+        )
+    }
+}
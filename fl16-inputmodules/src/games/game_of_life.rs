@@ -1,5 +1,15 @@
-use crate::control::{GameControlArg, GameOfLifeStartParam};
+use crate::control::{GameOfLifeOptions, GameOfLifeStartParam, LifeRules};
 use crate::matrix::{GameState, Grid, LedmatrixState, HEIGHT, WIDTH};
+use crate::patterns::draw;
+
+/// Generations to remember, to notice when the board repeats itself
+/// Oscillators with a longer period aren't noticed.
+const HISTORY: usize = 16;
+/// Generations to keep showing a repeating board, before auto restarting
+const RESTART_AFTER: u8 = 10;
+/// Every generation a cell gets older, it's shown a bit dimmer
+const AGE_DIMMING: u8 = 0x18;
+const MIN_AGE_BRIGHTNESS: u8 = 0x30;
 
 #[derive(Clone, Copy, num_derive::FromPrimitive, PartialEq, Eq)]
 pub enum Cell {
@@ -10,37 +20,124 @@ pub enum Cell {
 #[derive(Clone)]
 pub struct GameOfLifeState {
     cells: [[Cell; WIDTH]; HEIGHT],
+    /// Cells at the start, to restart from
+    seed: [[Cell; WIDTH]; HEIGHT],
+    /// Generations each cell has been alive for
+    ages: [[u8; WIDTH]; HEIGHT],
+    rules: LifeRules,
+    wrap: bool,
+    auto_restart: bool,
+    show_age: bool,
+    /// Hashes of the last generations
+    history: [Option<u32>; HISTORY],
+    pub generation: u32,
+    /// Generations in a row that repeated an earlier one
+    repeats: u8,
 }
 
 impl GameOfLifeState {
+    fn from_cells(cells: [[Cell; WIDTH]; HEIGHT]) -> Self {
+        GameOfLifeState {
+            cells,
+            seed: cells,
+            ages: [[0; WIDTH]; HEIGHT],
+            rules: LifeRules::default(),
+            wrap: true,
+            auto_restart: false,
+            show_age: false,
+            history: [None; HISTORY],
+            generation: 0,
+            repeats: 0,
+        }
+    }
+
     pub fn combine(&self, other: &Self) -> Self {
-        let mut state = self.clone();
+        let mut cells = self.cells;
         for x in 0..WIDTH {
             for y in 0..HEIGHT {
                 if other.cells[y][x] == Cell::Alive {
-                    state.cells[y][x] = Cell::Alive;
+                    cells[y][x] = Cell::Alive;
+                }
+            }
+        }
+        Self::from_cells(cells)
+    }
+
+    /// Start over from the same cells, with the same rules
+    pub fn restarted(&self) -> Self {
+        self.with_cells(self.seed)
+    }
+
+    /// Same rules, different cells. Starts counting generations again
+    fn with_cells(&self, cells: [[Cell; WIDTH]; HEIGHT]) -> Self {
+        GameOfLifeState {
+            seed: self.seed,
+            ..Self::from_cells(cells)
+        }
+        .with_options_of(self)
+    }
+
+    fn with_options_of(self, other: &Self) -> Self {
+        GameOfLifeState {
+            rules: other.rules,
+            wrap: other.wrap,
+            auto_restart: other.auto_restart,
+            show_age: other.show_age,
+            ..self
+        }
+    }
+
+    /// Random cells, about a third of them alive
+    /// There's only a single random byte, so it seeds a simple generator for the rest.
+    fn random_soup(&self, random: u8) -> [[Cell; WIDTH]; HEIGHT] {
+        let mut seed = u16::from_le_bytes([random, self.generation as u8]);
+        let mut cells = [[Cell::Dead; WIDTH]; HEIGHT];
+        for row in cells.iter_mut() {
+            for cell in row.iter_mut() {
+                seed = seed.wrapping_mul(25173).wrapping_add(13849);
+                if (seed >> 8).is_multiple_of(3) {
+                    *cell = Cell::Alive;
                 }
             }
         }
-        state
+        cells
+    }
+
+    /// FNV-1a over the cells, to compare generations cheaply
+    fn hash(&self) -> u32 {
+        let mut hash: u32 = 0x811c9dc5;
+        for row in &self.cells {
+            for cell in row {
+                hash = (hash ^ *cell as u32).wrapping_mul(0x01000193);
+            }
+        }
+        hash
+    }
+
+    /// Remember the current generation and count how long the board has been repeating
+    fn track_repeats(&mut self) {
+        let hash = Some(self.hash());
+        if self.history.contains(&hash) {
+            self.repeats = self.repeats.saturating_add(1);
+        } else {
+            self.repeats = 0;
+        }
+        self.history[self.generation as usize % HISTORY] = hash;
     }
 }
 
-pub fn start_game(state: &mut LedmatrixState, _random: u8, param: GameOfLifeStartParam) {
-    let gol = GameOfLifeState::new(param, &state.grid);
+pub fn start_game(state: &mut LedmatrixState, _random: u8, options: GameOfLifeOptions) {
+    let gol = GameOfLifeState::new(&options, &state.grid);
     state.grid = gol.draw_matrix();
     state.game = Some(GameState::GameOfLife(gol));
 }
-pub fn handle_control(state: &mut LedmatrixState, arg: &GameControlArg) {
-    if let Some(GameState::GameOfLife(ref mut _gol_state)) = state.game {
-        if let GameControlArg::Exit = arg {
-            state.game = None
-        }
-    }
-}
-pub fn game_step(state: &mut LedmatrixState, _random: u8) {
+pub fn game_step(state: &mut LedmatrixState, random: u8) {
     if let Some(GameState::GameOfLife(ref mut gol_state)) = state.game {
         gol_state.tick();
+        if gol_state.auto_restart && gol_state.repeats >= RESTART_AFTER {
+            // Dead, still or oscillating. Nothing interesting is going to happen anymore
+            *gol_state = gol_state.with_cells(gol_state.random_soup(random));
+        }
         state.grid = gol_state.draw_matrix();
     } else {
         panic!("Game of Life not started!")
@@ -49,25 +146,11 @@ pub fn game_step(state: &mut LedmatrixState, _random: u8) {
 
 impl GameOfLifeState {
     // TODO: Integrate Grid into GameOfLifeStartParam because it's only used in one of the enum variants
-    pub fn new(param: GameOfLifeStartParam, grid: &Grid) -> Self {
-        match param {
+    pub fn new(options: &GameOfLifeOptions, grid: &Grid) -> Self {
+        let gol = match options.pattern {
             GameOfLifeStartParam::Beacon => Self::beacon(),
-            GameOfLifeStartParam::CurrentMatrix => {
-                let mut cells = [[Cell::Dead; WIDTH]; HEIGHT];
-                for row in 0..HEIGHT {
-                    for col in 0..WIDTH {
-                        cells[row][col] = if grid.0[col][row] == 0 {
-                            Cell::Dead
-                        } else {
-                            Cell::Alive
-                        };
-                    }
-                }
-                //cells: grid
-                //    .0
-                //    .map(|col| col.map(|val| if val == 0 { Cell::Dead } else { Cell::Alive })),
-                GameOfLifeState { cells }
-            }
+            GameOfLifeStartParam::CurrentMatrix => Self::from_grid(grid),
+            GameOfLifeStartParam::Bitmap => Self::from_grid(&draw(&options.seed)),
             GameOfLifeStartParam::Pattern1 => Self::pattern1(),
             GameOfLifeStartParam::Blinker => Self::blinker(),
             GameOfLifeStartParam::Toad => Self::toad(),
@@ -75,8 +158,32 @@ impl GameOfLifeState {
             GameOfLifeStartParam::BeaconToadBlinker => Self::beacon()
                 .combine(&Self::toad())
                 .combine(&Self::blinker()),
+        };
+        GameOfLifeState {
+            rules: options.rules,
+            wrap: options.wrap,
+            auto_restart: options.auto_restart,
+            show_age: options.show_age,
+            ..gol
         }
     }
+    /// Every LED that's on is an alive cell
+    fn from_grid(grid: &Grid) -> Self {
+        let mut cells = [[Cell::Dead; WIDTH]; HEIGHT];
+        for row in 0..HEIGHT {
+            for col in 0..WIDTH {
+                cells[row][col] = if grid.0[col][row] == 0 {
+                    Cell::Dead
+                } else {
+                    Cell::Alive
+                };
+            }
+        }
+        //cells: grid
+        //    .0
+        //    .map(|col| col.map(|val| if val == 0 { Cell::Dead } else { Cell::Alive })),
+        Self::from_cells(cells)
+    }
     fn pattern1() -> Self {
         // Starts off with lots of alive cells, quickly reduced.
         // Eventually reaches a stable pattern without changes.
@@ -89,7 +196,7 @@ impl GameOfLifeState {
                 }
             }
         }
-        GameOfLifeState { cells }
+        Self::from_cells(cells)
     }
     fn blinker() -> Self {
         // Oscillates between:
@@ -105,7 +212,7 @@ impl GameOfLifeState {
         cells[8][5] = Cell::Alive;
         cells[8][6] = Cell::Alive;
         cells[8][7] = Cell::Alive;
-        GameOfLifeState { cells }
+        Self::from_cells(cells)
     }
     fn toad() -> Self {
         // Oscillates between
@@ -123,7 +230,7 @@ impl GameOfLifeState {
         cells[18][5] = Cell::Alive;
         cells[18][6] = Cell::Alive;
         cells[18][7] = Cell::Alive;
-        GameOfLifeState { cells }
+        Self::from_cells(cells)
     }
     fn beacon() -> Self {
         // Oscillates between
@@ -146,7 +253,7 @@ impl GameOfLifeState {
         cells[28][7] = Cell::Alive;
         cells[29][6] = Cell::Alive;
         cells[29][7] = Cell::Alive;
-        GameOfLifeState { cells }
+        Self::from_cells(cells)
     }
 
     fn glider() -> Self {
@@ -165,7 +272,7 @@ impl GameOfLifeState {
         cells[22][4] = Cell::Alive;
         cells[22][5] = Cell::Alive;
         cells[22][6] = Cell::Alive;
-        GameOfLifeState { cells }
+        Self::from_cells(cells)
     }
 
     /// Count live neighbor cells
@@ -181,6 +288,14 @@ impl GameOfLifeState {
 
                 let neighbor_row = (row + delta_row) % HEIGHT;
                 let neighbor_col = (col + delta_col) % WIDTH;
+                // Went around the edge, where there's nothing without wrapping
+                let wrapped = (row == 0 && delta_row == HEIGHT - 1)
+                    || (row == HEIGHT - 1 && delta_row == 1)
+                    || (col == 0 && delta_col == WIDTH - 1)
+                    || (col == WIDTH - 1 && delta_col == 1);
+                if wrapped && !self.wrap {
+                    continue;
+                }
 
                 count += self.cells[neighbor_row][neighbor_col] as u8;
             }
@@ -195,24 +310,29 @@ impl GameOfLifeState {
                 let cell = self.cells[row][col];
                 let live_neighbors = self.live_neighbor_count(row, col);
 
-                let child_cell = match (cell, live_neighbors) {
-                    // Fewer than 2 neighbors causes it to die
-                    (Cell::Alive, x) if x < 2 => Cell::Dead,
-                    // 2 or three neighbors are good and it stays alive
-                    (Cell::Alive, 2) | (Cell::Alive, 3) => Cell::Alive,
-                    // More than 3 is too many and the cell dies
-                    (Cell::Alive, x) if x > 3 => Cell::Dead,
-                    // 3 neighbors when the cell is dead, revives it
-                    (Cell::Dead, 3) => Cell::Alive,
-                    // No change by default
-                    (c, _) => c,
+                // By default B3/S23: 3 neighbors revive a dead cell,
+                // with 2 or 3 an alive cell stays alive, otherwise it dies
+                let enough = match cell {
+                    Cell::Alive => self.rules.survive,
+                    Cell::Dead => self.rules.birth,
+                };
+                let child_cell = if enough & (1 << live_neighbors) != 0 {
+                    Cell::Alive
+                } else {
+                    Cell::Dead
                 };
 
                 next_generation[row][col] = child_cell;
+                self.ages[row][col] = match (cell, child_cell) {
+                    (Cell::Alive, Cell::Alive) => self.ages[row][col].saturating_add(1),
+                    _ => 0,
+                };
             }
         }
 
         self.cells = next_generation;
+        self.generation = self.generation.wrapping_add(1);
+        self.track_repeats();
     }
 
     pub fn draw_matrix(&self) -> Grid {
@@ -220,7 +340,14 @@ impl GameOfLifeState {
 
         for row in 0..HEIGHT {
             for col in 0..WIDTH {
-                grid.0[col][row] = (self.cells[row][col] as u8) * 0xFF;
+                let brightness = if self.show_age {
+                    0xFF_u8
+                        .saturating_sub(self.ages[row][col].saturating_mul(AGE_DIMMING))
+                        .max(MIN_AGE_BRIGHTNESS)
+                } else {
+                    0xFF
+                };
+                grid.0[col][row] = (self.cells[row][col] as u8) * brightness;
             }
         }
 
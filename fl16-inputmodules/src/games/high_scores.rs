@@ -0,0 +1,96 @@
+//! Best scores of each game, kept in the persistent storage in flash
+//! See flash_layout.md for how they're stored.
+use crate::control::GameVal;
+use crate::flash::{self, PAGE_SIZE, PERSISTENT_STORAGE};
+
+/// Scores kept for each game
+pub const ENTRIES: usize = 5;
+/// Games with a score: Snake, Pong, Tetris
+const GAMES: usize = 3;
+const VERSION: u8 = 1;
+const TABLES_START: usize = 4;
+const CRC_START: usize = TABLES_START + GAMES * ENTRIES * 4;
+
+#[derive(Clone, Default)]
+pub struct HighScores {
+    /// Scores of each game, highest first. Unused entries are 0
+    tables: [[u32; ENTRIES]; GAMES],
+}
+
+impl HighScores {
+    /// Read from flash. Empty if nothing has been saved yet or it's corrupted
+    pub fn load() -> Self {
+        Self::from_bytes(flash::read_page(PERSISTENT_STORAGE)).unwrap_or_default()
+    }
+
+    /// Write to flash
+    pub fn save(&self) {
+        flash::write_page(PERSISTENT_STORAGE, &self.to_bytes());
+    }
+
+    fn from_bytes(bytes: &[u8; PAGE_SIZE]) -> Option<Self> {
+        if bytes[0] != VERSION {
+            return None;
+        }
+        let checksum = u32::from_le_bytes(bytes[CRC_START..CRC_START + 4].try_into().unwrap());
+        if checksum != crc32(&bytes[..CRC_START]) {
+            return None;
+        }
+        let mut high_scores = HighScores::default();
+        for (i, chunk) in bytes[TABLES_START..CRC_START].chunks(4).enumerate() {
+            high_scores.tables[i / ENTRIES][i % ENTRIES] =
+                u32::from_le_bytes(chunk.try_into().unwrap());
+        }
+        Some(high_scores)
+    }
+
+    fn to_bytes(&self) -> [u8; PAGE_SIZE] {
+        let mut bytes = [0xFF; PAGE_SIZE];
+        bytes[0] = VERSION;
+        bytes[1..TABLES_START].fill(0);
+        for (i, score) in self.tables.iter().flatten().enumerate() {
+            let start = TABLES_START + i * 4;
+            bytes[start..start + 4].copy_from_slice(&score.to_le_bytes());
+        }
+        let checksum = crc32(&bytes[..CRC_START]);
+        bytes[CRC_START..CRC_START + 4].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Scores of the game, highest first. Game of Life has no score
+    pub fn table(&self, game: GameVal) -> Option<&[u32; ENTRIES]> {
+        table_index(game).map(|i| &self.tables[i])
+    }
+
+    /// Put the score into the table of the game, if it's high enough
+    /// Returns its place in the table, 0 being the best.
+    pub fn record(&mut self, game: GameVal, score: u32) -> Option<usize> {
+        let table = &mut self.tables[table_index(game)?];
+        let place = table.iter().position(|&entry| score > entry)?;
+        table.copy_within(place..ENTRIES - 1, place + 1);
+        table[place] = score;
+        Some(place)
+    }
+
+    /// Clear the table of the game
+    pub fn reset(&mut self, game: GameVal) {
+        if let Some(i) = table_index(game) {
+            self.tables[i] = [0; ENTRIES];
+        }
+    }
+}
+
+fn table_index(game: GameVal) -> Option<usize> {
+    match game {
+        GameVal::Snake => Some(0),
+        GameVal::Pong => Some(1),
+        GameVal::Tetris => Some(2),
+        GameVal::GameOfLife => None,
+    }
+}
+
+/// Same checksum as the serial number
+fn crc32(bytes: &[u8]) -> u32 {
+    let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+    crc.checksum(bytes)
+}
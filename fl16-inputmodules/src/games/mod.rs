@@ -1,5 +1,185 @@
 pub mod game_of_life;
+pub mod high_scores;
 pub mod pong;
 pub mod pong_animation;
 pub mod snake;
 pub mod snake_animation;
+pub mod tetris;
+
+use crate::control::{GameControlArg, GameVal};
+use crate::matrix::{GameState, Grid, LedmatrixState};
+use crate::patterns::display_high_score;
+use tetris::TetrisState;
+
+/// Whether the game is still going
+#[derive(Copy, Clone, PartialEq)]
+pub enum GameStatus {
+    Running = 0,
+    Paused = 1,
+    GameOver = 2,
+    /// Game over, but the player won. Only in Snake, when the board is full
+    Won = 3,
+}
+
+impl GameState {
+    fn draw_matrix(&self) -> Grid {
+        match self {
+            GameState::Snake(snake) => snake.draw_matrix(),
+            GameState::Pong(pong) => pong.draw_matrix(),
+            GameState::Tetris(tetris) => tetris.draw_matrix(),
+            GameState::GameOfLife(gol) => gol.draw_matrix(),
+        }
+    }
+
+    /// Same game from the start. Random games get a new random start
+    fn restarted(&self, random: u8) -> Self {
+        match self {
+            GameState::Snake(snake) => GameState::Snake(snake.restarted(random)),
+            GameState::Pong(pong) => GameState::Pong(pong.restarted(random)),
+            GameState::Tetris(_) => GameState::Tetris(TetrisState::new(random)),
+            GameState::GameOfLife(gol) => GameState::GameOfLife(gol.restarted()),
+        }
+    }
+
+    /// Score to keep in the high scores, once the game is over
+    /// Pong only counts the player's points, when playing against the module.
+    fn final_score(&self) -> Option<(GameVal, u32)> {
+        match self {
+            GameState::Snake(snake) if snake.game_over => Some((GameVal::Snake, snake.score())),
+            GameState::Pong(pong) if pong.game_over && pong.ai => {
+                Some((GameVal::Pong, pong.score.upper as u32))
+            }
+            GameState::Tetris(tetris) if tetris.game_over => Some((GameVal::Tetris, tetris.score)),
+            _ => None,
+        }
+    }
+}
+
+/// Pausing, resuming and restarting work the same for every game
+/// Other controls are passed on to the running game, unless it's paused.
+pub fn handle_control(state: &mut LedmatrixState, arg: &GameControlArg, random: u8) {
+    let Some(ref game) = state.game else {
+        return;
+    };
+    match arg {
+        GameControlArg::Pause => state.game_paused = true,
+        GameControlArg::Resume => state.game_paused = false,
+        GameControlArg::Restart => {
+            let game = game.restarted(random);
+            state.grid = game.draw_matrix();
+            state.game = Some(game);
+            state.game_paused = false;
+            state.new_high_score = None;
+        }
+        GameControlArg::Exit => {
+            state.game = None;
+            state.game_paused = false;
+            state.new_high_score = None;
+        }
+        _ if state.game_paused => {}
+        _ => match game {
+            GameState::Snake(_) => snake::handle_control(state, arg),
+            GameState::Pong(_) => pong::handle_control(state, arg),
+            GameState::Tetris(_) => tetris::handle_control(state, arg),
+            // Can only be paused, restarted and exited
+            GameState::GameOfLife(_) => {}
+        },
+    }
+    state.game_paused_by_sleep = false;
+}
+
+/// Pause the game while the module sleeps, unless it's already paused
+pub fn pause_for_sleep(state: &mut LedmatrixState) {
+    if state.game.is_some() && !state.game_paused {
+        state.game_paused = true;
+        state.game_paused_by_sleep = true;
+    }
+}
+
+/// Resume the game after waking up, if it was paused by going to sleep
+pub fn resume_after_sleep(state: &mut LedmatrixState) {
+    if state.game_paused_by_sleep {
+        state.game_paused = false;
+        state.game_paused_by_sleep = false;
+    }
+}
+
+/// Once the game is over, put its score into the high scores and save them
+/// A new best score is celebrated by showing HI every other second.
+pub fn high_score_step(state: &mut LedmatrixState) {
+    if state.new_high_score.is_none() {
+        let Some((game, score)) = state.game.as_ref().and_then(GameState::final_score) else {
+            return;
+        };
+        let place = state.high_scores.record(game, score);
+        if place.is_some() {
+            state.high_scores.save();
+        }
+        state.new_high_score = Some(place == Some(0));
+    }
+    if state.new_high_score == Some(true) && state.frame / 32 % 2 == 1 {
+        state.grid = display_high_score();
+    }
+}
+
+/// Time between two steps of the running game, in microseconds
+/// Unless the host set it, games that speed up take shorter steps.
+pub fn step_period(state: &LedmatrixState) -> u64 {
+    if let Some(period) = state.game_step_period {
+        return period;
+    }
+    match state.game {
+        Some(GameState::Pong(ref pong_state)) => 100_000 - 5_000 * pong_state.speed,
+        Some(GameState::Snake(ref snake_state)) => 500_000 - 25_000 * snake_state.speed as u64,
+        Some(GameState::Tetris(ref tetris_state)) => {
+            500_000 - 40_000 * tetris_state.level().min(10) as u64
+        }
+        Some(GameState::GameOfLife(_)) => 500_000,
+        _ => 500_000,
+    }
+}
+
+/// Response to the GameStatus command
+/// Which game is running, whether it's paused or over and its score
+pub fn game_status(state: &LedmatrixState) -> [u8; 32] {
+    let mut response: [u8; 32] = [0; 32];
+    let Some(ref game) = state.game else {
+        response[0] = 0xFF;
+        return response;
+    };
+
+    let won = matches!(game, GameState::Snake(snake) if snake.won);
+    let (game, game_over) = match game {
+        GameState::Snake(snake) => {
+            let length = snake.body.len() as u16 + 1;
+            response[2..6].copy_from_slice(&snake.score().to_le_bytes());
+            response[10] = snake.speed;
+            response[11..13].copy_from_slice(&length.to_le_bytes());
+            (GameVal::Snake, snake.game_over)
+        }
+        GameState::Pong(pong) => {
+            response[2..6].copy_from_slice(&(pong.score.upper as u32).to_le_bytes());
+            response[6..10].copy_from_slice(&(pong.score.lower as u32).to_le_bytes());
+            response[10] = pong.speed as u8;
+            (GameVal::Pong, pong.game_over)
+        }
+        GameState::Tetris(tetris) => {
+            response[2..6].copy_from_slice(&tetris.score.to_le_bytes());
+            response[10] = tetris.level() as u8;
+            response[13..15].copy_from_slice(&(tetris.lines as u16).to_le_bytes());
+            (GameVal::Tetris, tetris.game_over)
+        }
+        GameState::GameOfLife(_) => (GameVal::GameOfLife, false),
+    };
+    response[0] = game as u8;
+    response[1] = if won {
+        GameStatus::Won
+    } else if game_over {
+        GameStatus::GameOver
+    } else if state.game_paused {
+        GameStatus::Paused
+    } else {
+        GameStatus::Running
+    } as u8;
+    response
+}
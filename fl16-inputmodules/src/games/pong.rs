@@ -1,12 +1,18 @@
-use crate::control::GameControlArg;
+use crate::control::{GameControlArg, PongStartParam};
 use crate::matrix::{GameState, Grid, LedmatrixState, HEIGHT, WIDTH};
+use crate::patterns::display_scores;
 
 const PADDLE_WIDTH: usize = 5;
+/// The ball gets faster with every hit, up to this speed
+const MAX_SPEED: u64 = 10;
 
+/// Points of each player, for every time the other one missed the ball
 #[derive(Clone)]
-struct Score {
-    _upper: u8,
-    _lower: u8,
+pub struct Score {
+    /// Player at the top, moved with Left and Right
+    pub upper: u8,
+    /// Player at the bottom, moved with SecondLeft and SecondRight
+    pub lower: u8,
 }
 
 type Position = (usize, usize);
@@ -21,31 +27,80 @@ struct Ball {
 
 #[derive(Clone)]
 pub struct PongState {
-    // TODO: Properly calculate score and display it
-    _score: Score,
+    pub score: Score,
     ball: Ball,
     paddles: (usize, usize),
     pub speed: u64,
+    /// Points that win the match. 0 to keep playing forever
+    points_to_win: u8,
+    /// Whether the lower paddle is moved by the module, instead of a second player
+    pub ai: bool,
+    pub game_over: bool,
 }
 
 impl Default for PongState {
     fn default() -> Self {
         PongState {
-            _score: Score {
-                _upper: 0,
-                _lower: 0,
-            },
+            score: Score { upper: 0, lower: 0 },
             ball: Ball {
                 pos: (4, 20),
                 direction: (0, 1),
             },
             paddles: (PADDLE_WIDTH / 2, PADDLE_WIDTH / 2),
             speed: 0,
+            points_to_win: 0,
+            ai: false,
+            game_over: false,
         }
     }
 }
 
 impl PongState {
+    pub fn new(param: PongStartParam, random: u8) -> Self {
+        let mut pong = PongState {
+            points_to_win: param.points_to_win,
+            ai: param.players < 2,
+            ..Default::default()
+        };
+        pong.serve(random);
+        pong
+    }
+
+    /// Same match from the start, with the same players
+    pub fn restarted(&self, random: u8) -> Self {
+        let mut pong = PongState {
+            points_to_win: self.points_to_win,
+            ai: self.ai,
+            ..Default::default()
+        };
+        pong.serve(random);
+        pong
+    }
+
+    /// Start again from the center, in a random direction
+    fn serve(&mut self, random: u8) {
+        self.speed = 0;
+        self.ball = Ball {
+            pos: (WIDTH / 2, HEIGHT / 2),
+            direction: random_v(random),
+        };
+    }
+
+    /// Follow the ball with the lower paddle, while it's coming closer
+    /// Sometimes it's too slow, so that it can be beaten.
+    fn move_ai(&mut self, random: u8) {
+        let (_, vy) = self.ball.direction;
+        if vy <= 0 || random & 0x03 == 0 {
+            return;
+        }
+        let center = self.paddles.1 + PADDLE_WIDTH / 2;
+        if center < self.ball.pos.0 && self.paddles.1 + PADDLE_WIDTH < WIDTH {
+            self.paddles.1 += 1;
+        } else if center > self.ball.pos.0 && self.paddles.1 >= 1 {
+            self.paddles.1 -= 1;
+        }
+    }
+
     pub fn draw_matrix(&self) -> Grid {
         let mut grid = Grid::default();
 
@@ -60,7 +115,14 @@ impl PongState {
         grid
     }
 
-    pub fn tick(&mut self) {
+    pub fn tick(&mut self, random: u8) {
+        if self.game_over {
+            return;
+        }
+        if self.ai {
+            self.move_ai(random);
+        }
+
         self.ball.pos = {
             let (vx, vy) = self.ball.direction;
             let (x, y) = add_velocity(self.ball.pos, self.ball.direction);
@@ -84,13 +146,21 @@ impl PongState {
                     // Shouldn't occur
                     _ => (vx, -vy),
                 };
-                // TODO: Not sure if I want the speed to change. Speed by angle change is already high enough
-                //self.speed += 1;
+                self.speed = (self.speed + 1).min(MAX_SPEED);
                 (x, y)
             } else if y == 0 || y == HEIGHT - 1 {
-                self.speed = 0;
-                self.ball.direction = (1, 1); //random_v(random);
-                (WIDTH / 2, HEIGHT / 2)
+                // Missed the ball, the other player gets a point
+                let winner = if y == 0 {
+                    &mut self.score.lower
+                } else {
+                    &mut self.score.upper
+                };
+                *winner = winner.saturating_add(1);
+                if self.points_to_win > 0 && *winner >= self.points_to_win {
+                    self.game_over = true;
+                }
+                self.serve(random);
+                self.ball.pos
             } else {
                 (x, y)
             };
@@ -99,49 +169,42 @@ impl PongState {
     }
     pub fn handle_control(&mut self, arg: &GameControlArg) {
         match arg {
-            GameControlArg::Left => {
-                if self.paddles.0 + PADDLE_WIDTH < WIDTH {
-                    self.paddles.0 += 1;
-                }
+            GameControlArg::Left if self.paddles.0 + PADDLE_WIDTH < WIDTH => {
+                self.paddles.0 += 1;
             }
-            GameControlArg::Right => {
-                if self.paddles.0 >= 1 {
-                    self.paddles.0 -= 1;
-                }
+            GameControlArg::Right if self.paddles.0 >= 1 => {
+                self.paddles.0 -= 1;
             }
-            GameControlArg::SecondLeft => {
-                if self.paddles.1 + PADDLE_WIDTH < WIDTH {
-                    self.paddles.1 += 1;
-                }
+            // The second player joined, the module stops playing for them
+            GameControlArg::SecondLeft | GameControlArg::SecondRight if self.ai => {
+                self.ai = false;
+                self.handle_control(arg);
             }
-            GameControlArg::SecondRight => {
-                if self.paddles.1 >= 1 {
-                    self.paddles.1 -= 1;
-                }
+            GameControlArg::SecondLeft if self.paddles.1 + PADDLE_WIDTH < WIDTH => {
+                self.paddles.1 += 1;
+            }
+            GameControlArg::SecondRight if self.paddles.1 >= 1 => {
+                self.paddles.1 -= 1;
             }
             _ => {}
         }
     }
 }
 
-pub fn start_game(state: &mut LedmatrixState, _random: u8) {
-    state.game = Some(GameState::Pong(PongState::default()))
+pub fn start_game(state: &mut LedmatrixState, random: u8, param: PongStartParam) {
+    state.game = Some(GameState::Pong(PongState::new(param, random)))
 }
 pub fn handle_control(state: &mut LedmatrixState, arg: &GameControlArg) {
     if let Some(GameState::Pong(ref mut pong_state)) = state.game {
-        match arg {
-            GameControlArg::Exit => state.game = None,
-            _ => pong_state.handle_control(arg),
-        }
+        pong_state.handle_control(arg);
     }
 }
 
-// TODO: Randomize the velocity vector upon respawning
-fn _random_v(random: u8) -> Velocity {
-    // TODO: while food == head:
-    let x = ((random & 0xF0) >> 4) % WIDTH as u8;
-    let y = (random & 0x0F) % HEIGHT as u8;
-    (x as i8, y as i8)
+/// Up or down, straight or diagonally
+fn random_v(random: u8) -> Velocity {
+    let vx = (random % 3) as i8 - 1;
+    let vy = if random & 0x80 == 0 { 1 } else { -1 };
+    (vx, vy)
 }
 
 fn add_velocity(pos: Position, v: Velocity) -> Position {
@@ -161,9 +224,13 @@ fn hit_paddle(ball: Position, paddles: (usize, usize)) -> Option<usize> {
     }
 }
 
-pub fn game_step(state: &mut LedmatrixState, _random: u8) {
+pub fn game_step(state: &mut LedmatrixState, random: u8) {
     if let Some(GameState::Pong(ref mut pong_state)) = state.game {
-        pong_state.tick();
-        state.grid = pong_state.draw_matrix();
+        pong_state.tick(random);
+        state.grid = if pong_state.game_over {
+            display_scores(pong_state.score.upper, pong_state.score.lower)
+        } else {
+            pong_state.draw_matrix()
+        };
     }
 }
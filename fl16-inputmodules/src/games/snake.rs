@@ -1,10 +1,15 @@
-use crate::control::GameControlArg;
+use crate::control::{GameControlArg, SnakeStartParam};
 use crate::matrix::{GameState, Grid, LedmatrixState, HEIGHT, LEDS, WIDTH};
+use crate::patterns::display_score;
 
 use heapless::Vec;
 
-// Wrap around the edges
-const WRAP_ENABLE: bool = false;
+/// Speed levels, every level makes a step 25ms shorter
+pub const MAX_SPEED: u8 = 16;
+/// Obstacles beyond this are ignored
+pub const MAX_OBSTACLES: usize = 32;
+const OBSTACLE_BRIGHTNESS: u8 = 0x40;
+const START: Position = (4, 0);
 
 #[derive(Clone, Debug, Copy)]
 pub enum HeadDirection {
@@ -23,19 +28,76 @@ pub struct SnakeState {
     // Unrealistic that the body will ever get this long
     pub body: Vec<Position, LEDS>,
     pub game_over: bool,
+    /// The snake filled the whole board
+    pub won: bool,
     food: Position,
+    obstacles: Vec<Position, MAX_OBSTACLES>,
+    /// Current speed level, up to MAX_SPEED
+    pub speed: u8,
+    param: SnakeStartParam,
 }
 
 impl SnakeState {
-    pub fn new(random: u8) -> Self {
-        SnakeState {
-            head: (4, 0),
+    pub fn new(param: SnakeStartParam, random: u8) -> Self {
+        let mut snake = SnakeState {
+            head: START,
             direction: HeadDirection::Down,
             body: Vec::new(),
             game_over: false,
-            food: place_food(random),
+            won: false,
+            food: START,
+            obstacles: Vec::new(),
+            speed: param.speed.min(MAX_SPEED),
+            param,
+        };
+        snake.place_obstacles(random);
+        // There's always space left for the food at the start
+        snake.food = snake.place_food(random).unwrap_or(START);
+        snake
+    }
+
+    /// Same rules, new random start
+    pub fn restarted(&self, random: u8) -> Self {
+        SnakeState::new(self.param, random)
+    }
+
+    fn occupied(&self, pos: Position) -> bool {
+        pos == self.head || self.body.contains(&pos) || self.obstacles.contains(&pos)
+    }
+
+    /// Scatter the obstacles over the board
+    /// There's only a single random byte, so it seeds a simple generator for the rest.
+    fn place_obstacles(&mut self, random: u8) {
+        let mut seed = random as u16;
+        for _ in 0..(self.param.obstacles as usize).min(MAX_OBSTACLES) {
+            seed = seed.wrapping_mul(25173).wrapping_add(13849);
+            let Some(pos) = self.free_cell(seed as usize % LEDS) else {
+                break;
+            };
+            // Keep the way free, where the snake starts
+            if pos.0 == START.0 && pos.1 < 5 {
+                continue;
+            }
+            self.obstacles.push(pos).unwrap();
         }
     }
+
+    /// First cell that's not occupied, starting from the index. None if the board is full
+    fn free_cell(&self, start: usize) -> Option<Position> {
+        (0..LEDS)
+            .map(|i| {
+                let cell = (start + i) % LEDS;
+                ((cell % WIDTH) as i8, (cell / WIDTH) as i8)
+            })
+            .find(|pos| !self.occupied(*pos))
+    }
+
+    /// Food goes where the random byte points, or the next free cell after it
+    /// The byte is spread over the whole board, so food can show up anywhere.
+    fn place_food(&self, random: u8) -> Option<Position> {
+        self.free_cell(random as usize * LEDS / 256)
+    }
+
     pub fn tick(&mut self, random: u8) {
         if self.game_over {
             return;
@@ -43,41 +105,42 @@ impl SnakeState {
 
         let (x, y) = self.head;
         let oldhead = self.head;
-        self.head = match self.direction {
+        let (x, y) = match self.direction {
             // (0, 0) is at the top right corner
             HeadDirection::Right => (x - 1, y),
             HeadDirection::Left => (x + 1, y),
             HeadDirection::Down => (x, y + 1),
             HeadDirection::Up => (x, y - 1),
         };
-        let (x, y) = self.head;
         let width = WIDTH as i8;
         let height = HEIGHT as i8;
 
-        if self.body.contains(&self.head) {
-            // Ran into itself
-            self.game_over = true
-        } else if x >= width || x < 0 || y >= height || y < 0 {
+        let out_of_bounds = x >= width || x < 0 || y >= height || y < 0;
+        if out_of_bounds && !self.param.wrap {
             // Hit an edge
-            if WRAP_ENABLE {
-                self.head = if x >= width {
-                    (0, y)
-                } else if x < 0 {
-                    (width - 1, y)
-                } else if y >= height {
-                    (x, 0)
-                } else if y < 0 {
-                    (x, height - 1)
-                } else {
-                    (x, y)
-                };
-            } else {
-                self.game_over = true
-            }
+            self.game_over = true;
+            return;
+        }
+        self.head = (x.rem_euclid(width), y.rem_euclid(height));
+
+        if self.body.contains(&self.head) || self.obstacles.contains(&self.head) {
+            // Ran into itself or an obstacle
+            self.game_over = true
         } else if self.head == self.food {
             // Eating food and growing
             self.body.insert(0, oldhead).unwrap();
-            self.food = place_food(random);
+            self.speed = self
+                .speed
+                .saturating_add(self.param.speed_up)
+                .min(MAX_SPEED);
+            match self.place_food(random) {
+                Some(food) => self.food = food,
+                None => {
+                    // No space left, the snake fills the whole board
+                    self.won = true;
+                    self.game_over = true;
+                }
+            }
         } else if !self.body.is_empty() {
             // Move body along
             self.body.pop();
@@ -85,6 +148,11 @@ impl SnakeState {
         }
     }
 
+    /// One point for every food that was eaten
+    pub fn score(&self) -> u32 {
+        self.body.len() as u32
+    }
+
     pub fn handle_control(&mut self, arg: &GameControlArg) {
         match arg {
             GameControlArg::Up => self.direction = HeadDirection::Up,
@@ -100,6 +168,9 @@ impl SnakeState {
 
         grid.0[x as usize][y as usize] = 0xFF;
         grid.0[self.food.0 as usize][self.food.1 as usize] = 0xFF;
+        for (x, y) in &self.obstacles {
+            grid.0[*x as usize][*y as usize] = OBSTACLE_BRIGHTNESS;
+        }
         for bodypart in &self.body {
             let (x, y) = bodypart;
             grid.0[*x as usize][*y as usize] = 0xFF;
@@ -109,23 +180,13 @@ impl SnakeState {
     }
 }
 
-fn place_food(random: u8) -> Position {
-    // TODO: while food == head:
-    let x = ((random & 0xF0) >> 4) % WIDTH as u8;
-    let y = (random & 0x0F) % HEIGHT as u8;
-    (x as i8, y as i8)
-}
-
-pub fn start_game(state: &mut LedmatrixState, random: u8) {
-    state.game = Some(GameState::Snake(SnakeState::new(random)));
+pub fn start_game(state: &mut LedmatrixState, random: u8, param: SnakeStartParam) {
+    state.game = Some(GameState::Snake(SnakeState::new(param, random)));
 }
 
 pub fn handle_control(state: &mut LedmatrixState, arg: &GameControlArg) {
     if let Some(GameState::Snake(ref mut snake_state)) = state.game {
-        match arg {
-            GameControlArg::Exit => state.game = None,
-            _ => snake_state.handle_control(arg),
-        }
+        snake_state.handle_control(arg);
     }
 }
 
@@ -133,9 +194,11 @@ pub fn game_step(state: &mut LedmatrixState, random: u8) -> (HeadDirection, bool
     if let Some(GameState::Snake(ref mut snake_state)) = state.game {
         snake_state.tick(random);
 
-        if !snake_state.game_over {
-            state.grid = snake_state.draw_matrix();
-        }
+        state.grid = if snake_state.game_over {
+            display_score(snake_state.score())
+        } else {
+            snake_state.draw_matrix()
+        };
         (
             snake_state.direction,
             snake_state.game_over,
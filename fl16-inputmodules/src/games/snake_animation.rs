@@ -1,4 +1,4 @@
-use crate::control::GameControlArg;
+use crate::control::{GameControlArg, SnakeStartParam};
 use crate::games::snake::SnakeState;
 use crate::matrix::Grid;
 
@@ -11,15 +11,16 @@ pub struct SnakeIterator {
 impl SnakeIterator {
     pub fn new(random: u8) -> Self {
         Self {
-            state: SnakeState::new(random),
+            state: SnakeState::new(SnakeStartParam::default(), random),
             commands: SAMPLE_GAME,
             current_tick: 0,
         }
     }
 }
 impl Default for SnakeIterator {
+    /// Puts the first food at (1, 15), where SAMPLE_GAME goes to eat it
     fn default() -> Self {
-        Self::new(31)
+        Self::new(114)
     }
 }
 
@@ -50,6 +51,7 @@ impl Iterator for SnakeIterator {
 }
 
 // TODO: Plan out a nice looking game
+/// The random byte of the step, when the snake eats, decides where the next food goes
 const SAMPLE_GAME: [(Option<GameControlArg>, u8); 64] = [
     (Some(GameControlArg::Down), 0),
     (None, 0),
@@ -76,7 +78,7 @@ const SAMPLE_GAME: [(Option<GameControlArg>, u8); 64] = [
     (None, 0),
     (Some(GameControlArg::Down), 0),
     (None, 0),
-    (None, 10),
+    (None, 76),
     (None, 0),
     (None, 0),
     (Some(GameControlArg::Right), 0),
@@ -116,3 +118,15 @@ const SAMPLE_GAME: [(Option<GameControlArg>, u8); 64] = [
     (None, 0),
     (None, 0),
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_game_eats() {
+        let mut snake = SnakeIterator::default();
+        for _ in snake.by_ref() {}
+        assert_eq!(snake.state.score(), 2);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::control::GameControlArg;
+use crate::matrix::{GameState, Grid, LedmatrixState, HEIGHT, WIDTH};
+use crate::patterns::display_score;
+
+/// Lines to clear before the next level starts and the pieces fall faster
+const LINES_PER_LEVEL: u32 = 10;
+/// Points for clearing 0-4 lines at once, multiplied by the level plus one
+const LINE_POINTS: [u32; 5] = [0, 40, 100, 300, 1200];
+/// Sideways offsets to try, when a rotated piece doesn't fit where it is
+const WALL_KICKS: [i8; 5] = [0, 1, -1, 2, -2];
+
+const PIECE_BRIGHTNESS: u8 = 0xFF;
+const BOARD_BRIGHTNESS: u8 = 0x60;
+
+type Position = (i8, i8);
+
+/// Blocks of each tetromino: I, O, T, S, Z, J and L
+/// Relative to the center of rotation, as seen by the user: x to the right, y down.
+const TETROMINOES: [[Position; 4]; 7] = [
+    [(-1, 0), (0, 0), (1, 0), (2, 0)],
+    [(0, 0), (1, 0), (0, 1), (1, 1)],
+    [(-1, 0), (0, 0), (1, 0), (0, -1)],
+    [(0, 0), (1, 0), (-1, 1), (0, 1)],
+    [(-1, 0), (0, 0), (0, 1), (1, 1)],
+    [(-1, -1), (-1, 0), (0, 0), (1, 0)],
+    [(1, -1), (-1, 0), (0, 0), (1, 0)],
+];
+/// The O piece looks the same in every rotation
+const O_PIECE: usize = 1;
+
+#[derive(Clone, Copy)]
+struct Piece {
+    /// Index into TETROMINOES
+    shape: usize,
+    /// Quarter turns clockwise
+    rotation: u8,
+    /// Center of rotation on the board
+    pos: Position,
+}
+
+impl Piece {
+    fn new(random: u8) -> Self {
+        Piece {
+            shape: random as usize % TETROMINOES.len(),
+            rotation: 0,
+            pos: ((WIDTH / 2) as i8, 1),
+        }
+    }
+
+    /// Positions of the blocks on the board
+    fn blocks(&self) -> [Position; 4] {
+        let rotations = if self.shape == O_PIECE {
+            0
+        } else {
+            self.rotation
+        };
+        TETROMINOES[self.shape].map(|(mut x, mut y)| {
+            for _ in 0..rotations {
+                (x, y) = (-y, x);
+            }
+            // (0, 0) is at the top right corner, so x is mirrored
+            (self.pos.0 - x, self.pos.1 + y)
+        })
+    }
+
+    fn moved(&self, dx: i8, dy: i8) -> Self {
+        Piece {
+            pos: (self.pos.0 + dx, self.pos.1 + dy),
+            ..*self
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct TetrisState {
+    /// Blocks that have landed, row by row from the top
+    board: [[bool; WIDTH]; HEIGHT],
+    piece: Piece,
+    pub score: u32,
+    pub lines: u32,
+    pub game_over: bool,
+}
+
+impl TetrisState {
+    pub fn new(random: u8) -> Self {
+        TetrisState {
+            board: [[false; WIDTH]; HEIGHT],
+            piece: Piece::new(random),
+            score: 0,
+            lines: 0,
+            game_over: false,
+        }
+    }
+
+    /// Starts at 0, the pieces fall faster with every level
+    pub fn level(&self) -> u32 {
+        self.lines / LINES_PER_LEVEL
+    }
+
+    /// Whether the piece is within the board and doesn't overlap any blocks
+    fn fits(&self, piece: &Piece) -> bool {
+        piece.blocks().iter().all(|&(x, y)| {
+            (0..WIDTH as i8).contains(&x)
+                && (0..HEIGHT as i8).contains(&y)
+                && !self.board[y as usize][x as usize]
+        })
+    }
+
+    /// Move the piece, if there's space. Returns whether it moved
+    fn try_move(&mut self, dx: i8, dy: i8) -> bool {
+        let moved = self.piece.moved(dx, dy);
+        let fits = self.fits(&moved);
+        if fits {
+            self.piece = moved;
+        }
+        fits
+    }
+
+    fn rotate(&mut self) {
+        let rotated = Piece {
+            rotation: (self.piece.rotation + 1) % 4,
+            ..self.piece
+        };
+        if let Some(kicked) = WALL_KICKS
+            .iter()
+            .map(|dx| rotated.moved(*dx, 0))
+            .find(|piece| self.fits(piece))
+        {
+            self.piece = kicked;
+        }
+    }
+
+    /// Remove full rows, let the ones above fall down and count the points
+    fn clear_lines(&mut self) {
+        let mut cleared = 0;
+        for y in 0..HEIGHT {
+            if self.board[y].iter().all(|block| *block) {
+                self.board.copy_within(0..y, 1);
+                self.board[0] = [false; WIDTH];
+                cleared += 1;
+            }
+        }
+        self.score += LINE_POINTS[cleared] * (self.level() + 1);
+        self.lines += cleared as u32;
+    }
+
+    /// Let the piece fall by a row. If it lands, the next one starts at the top
+    pub fn tick(&mut self, random: u8) {
+        if self.game_over || self.try_move(0, 1) {
+            return;
+        }
+
+        for (x, y) in self.piece.blocks() {
+            self.board[y as usize][x as usize] = true;
+        }
+        self.clear_lines();
+
+        self.piece = Piece::new(random);
+        if !self.fits(&self.piece) {
+            // No space left at the top
+            self.game_over = true;
+        }
+    }
+
+    pub fn handle_control(&mut self, arg: &GameControlArg) {
+        match arg {
+            GameControlArg::Up => self.rotate(),
+            GameControlArg::Down => {
+                self.try_move(0, 1);
+            }
+            // (0, 0) is at the top right corner
+            GameControlArg::Left => {
+                self.try_move(1, 0);
+            }
+            GameControlArg::Right => {
+                self.try_move(-1, 0);
+            }
+            _ => {}
+        }
+    }
+
+    /// Landed blocks are dimmed, the falling piece is bright
+    pub fn draw_matrix(&self) -> Grid {
+        let mut grid = Grid::default();
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, block) in row.iter().enumerate() {
+                if *block {
+                    grid.0[x][y] = BOARD_BRIGHTNESS;
+                }
+            }
+        }
+        for (x, y) in self.piece.blocks() {
+            grid.0[x as usize][y as usize] = PIECE_BRIGHTNESS;
+        }
+
+        grid
+    }
+}
+
+pub fn start_game(state: &mut LedmatrixState, random: u8) {
+    let tetris = TetrisState::new(random);
+    state.grid = tetris.draw_matrix();
+    state.game = Some(GameState::Tetris(tetris));
+}
+
+pub fn handle_control(state: &mut LedmatrixState, arg: &GameControlArg) {
+    if let Some(GameState::Tetris(ref mut tetris_state)) = state.game {
+        if !tetris_state.game_over {
+            tetris_state.handle_control(arg);
+            state.grid = tetris_state.draw_matrix();
+        }
+    }
+}
+
+pub fn game_step(state: &mut LedmatrixState, random: u8) {
+    if let Some(GameState::Tetris(ref mut tetris_state)) = state.game {
+        tetris_state.tick(random);
+
+        state.grid = if tetris_state.game_over {
+            display_score(tetris_state.score)
+        } else {
+            tetris_state.draw_matrix()
+        };
+    }
+}
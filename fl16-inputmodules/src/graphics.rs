@@ -2,13 +2,18 @@ use embedded_graphics::pixelcolor::Rgb565;
 use embedded_graphics::prelude::*;
 use embedded_graphics::{
     image::Image,
-    mono_font::{ascii::FONT_9X15, MonoTextStyle},
-    primitives::{PrimitiveStyle, Rectangle},
-    text::Text,
+    mono_font::{
+        ascii::{FONT_10X20, FONT_4X6, FONT_6X10, FONT_9X15, FONT_9X18_BOLD},
+        MonoFont, MonoTextStyle, MonoTextStyleBuilder,
+    },
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
 };
 
 use tinybmp::Bmp;
 
+use crate::clock::*;
+
 pub const LOGO_OFFSET_X: i32 = 100;
 pub const LOGO_OFFSET_Y: i32 = 100;
 
@@ -42,6 +47,221 @@ where
     Ok(())
 }
 
+/// Fonts that text can be drawn with
+#[derive(Copy, Clone, num_derive::FromPrimitive)]
+pub enum TextFont {
+    Font4x6 = 0x00,
+    Font6x10 = 0x01,
+    /// Same as SetText
+    Font9x15 = 0x02,
+    Font9x18Bold = 0x03,
+    Font10x20 = 0x04,
+}
+
+impl TextFont {
+    pub fn mono_font(self) -> &'static MonoFont<'static> {
+        match self {
+            TextFont::Font4x6 => &FONT_4X6,
+            TextFont::Font6x10 => &FONT_6X10,
+            TextFont::Font9x15 => &FONT_9X15,
+            TextFont::Font9x18Bold => &FONT_9X18_BOLD,
+            TextFont::Font10x20 => &FONT_10X20,
+        }
+    }
+}
+
+/// Where and how to draw text
+#[derive(Copy, Clone)]
+pub struct TextLayout {
+    /// Top of the first line. Horizontally the left edge, center or right edge,
+    /// depending on the alignment
+    pub position: Point,
+    pub font: TextFont,
+    pub alignment: Alignment,
+    /// Wrap lines that are wider than this many pixels. 0 to not wrap
+    pub wrap_width: u32,
+    /// White text on black background
+    pub inverted: bool,
+}
+
+/// Splits text into lines at line breaks and, if they're too long, at spaces
+struct WrappedLines<'a> {
+    rest: &'a str,
+    max_chars: usize,
+}
+
+impl<'a> Iterator for WrappedLines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let line_end = self.rest.find('\n').unwrap_or(self.rest.len());
+        let line = &self.rest[..line_end];
+        let (line, consumed) = match line.char_indices().nth(self.max_chars) {
+            // Fits, skip over the line break
+            None => (line, (line_end + 1).min(self.rest.len())),
+            Some((cut, _)) => {
+                // Break at the last space that still fits. Cut words that
+                // are too long for a line on their own.
+                let space = if line[cut..].starts_with(' ') {
+                    Some(cut)
+                } else {
+                    line[..cut].rfind(' ')
+                };
+                match space {
+                    Some(space) if space > 0 => (&line[..space], space + 1),
+                    _ => (&line[..cut], cut),
+                }
+            }
+        };
+        self.rest = &self.rest[consumed..];
+        Some(line.trim_end())
+    }
+}
+
+/// Draw text with the given layout, wrapping it if needed
+pub fn draw_text_layout<D>(target: &mut D, text: &str, layout: &TextLayout) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let font = layout.font.mono_font();
+    let (fg, bg) = if layout.inverted {
+        (Rgb565::WHITE, Rgb565::BLACK)
+    } else {
+        (Rgb565::BLACK, Rgb565::WHITE)
+    };
+    // Fill the background, so that new text fully replaces the old one
+    let character_style = MonoTextStyleBuilder::new()
+        .font(font)
+        .text_color(fg)
+        .background_color(bg)
+        .build();
+    let text_style = TextStyleBuilder::new()
+        .alignment(layout.alignment)
+        .baseline(Baseline::Top)
+        .build();
+
+    let char_width = font.character_size.width + font.character_spacing;
+    let max_chars = if layout.wrap_width == 0 {
+        usize::MAX
+    } else {
+        (layout.wrap_width / char_width).max(1) as usize
+    };
+    let lines = WrappedLines {
+        rest: text,
+        max_chars,
+    };
+
+    for (i, line) in lines.enumerate() {
+        let position =
+            layout.position + Point::new(0, (i as u32 * font.character_size.height) as i32);
+        Text::with_text_style(line, position, character_style, text_style).draw(target)?;
+    }
+
+    Ok(())
+}
+
+/// Fill an area with a single color
+pub fn clear_region<D>(target: &mut D, area: Rectangle, color: Rgb565) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    area.into_styled(PrimitiveStyle::with_fill(color))
+        .draw(target)
+}
+
+/// Draw HH:MM over the whole screen, in large digits
+pub fn draw_digital_clock<D>(target: &mut D, hours: u8, minutes: u8) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    // Each pixel of a glyph is drawn as a square
+    const SCALE: i32 = 9;
+    let screen = target.bounding_box();
+    target.fill_solid(&screen, Rgb565::WHITE)?;
+
+    let glyph_width = GLYPH_WIDTH as i32 * SCALE;
+    let width = 5 * glyph_width + 4 * SCALE;
+    let height = GLYPH_HEIGHT as i32 * SCALE;
+    let origin = screen.center() - Point::new(width / 2, height / 2);
+
+    for (i, glyph) in clock_glyphs(hours, minutes).iter().enumerate() {
+        let glyph_origin = origin + Point::new(i as i32 * (glyph_width + SCALE), 0);
+        for y in 0..GLYPH_HEIGHT {
+            for x in 0..GLYPH_WIDTH {
+                if glyph_pixel(glyph, x, y) {
+                    let pixel = Rectangle::new(
+                        glyph_origin + Point::new(x as i32, y as i32) * SCALE,
+                        Size::new(SCALE as u32, SCALE as u32),
+                    );
+                    target.fill_solid(&pixel, Rgb565::BLACK)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// sin of 0 to 90 degrees in steps of 6 degrees, times 1000
+const QUARTER_SINE: [i32; 16] = [
+    0, 105, 208, 309, 407, 500, 588, 669, 743, 809, 866, 914, 951, 978, 995, 1000,
+];
+
+/// Point on a dial, at a position out of 60, clockwise from the top
+fn dial_point(center: Point, radius: i32, position: u32) -> Point {
+    let sine = |position: u32| match position % 60 {
+        i @ 0..=15 => QUARTER_SINE[i as usize],
+        i @ 16..=30 => QUARTER_SINE[30 - i as usize],
+        i @ 31..=45 => -QUARTER_SINE[i as usize - 30],
+        i => -QUARTER_SINE[60 - i as usize],
+    };
+    let cosine = sine(position + 15);
+    center + Point::new(radius * sine(position) / 1000, -radius * cosine / 1000)
+}
+
+/// Draw a dial with hour and minute hands over the whole screen
+pub fn draw_analog_clock<D>(target: &mut D, hours: u8, minutes: u8) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = Rgb565>,
+{
+    let screen = target.bounding_box();
+    target.fill_solid(&screen, Rgb565::WHITE)?;
+
+    let center = screen.center();
+    let radius = screen.size.width.min(screen.size.height) as i32 / 2 - 20;
+    Circle::with_center(center, 2 * radius as u32)
+        .into_styled(PrimitiveStyle::with_stroke(Rgb565::BLACK, 4))
+        .draw(target)?;
+
+    // Marks for the hours
+    for position in (0..60).step_by(5) {
+        Line::new(
+            dial_point(center, radius * 85 / 100, position),
+            dial_point(center, radius, position),
+        )
+        .into_styled(PrimitiveStyle::with_stroke(Rgb565::BLACK, 4))
+        .draw(target)?;
+    }
+
+    // The hour hand moves on between the hours
+    let hour_position = (hours % 12) as u32 * 5 + minutes as u32 / 12;
+    Line::new(center, dial_point(center, radius / 2, hour_position))
+        .into_styled(PrimitiveStyle::with_stroke(Rgb565::BLACK, 8))
+        .draw(target)?;
+    Line::new(center, dial_point(center, radius * 8 / 10, minutes as u32))
+        .into_styled(PrimitiveStyle::with_stroke(Rgb565::BLACK, 4))
+        .draw(target)?;
+    Circle::with_center(center, 12)
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::BLACK))
+        .draw(target)?;
+
+    Ok(())
+}
+
 pub fn draw_logo<D>(target: &mut D, offset: Point) -> Result<Rectangle, D::Error>
 where
     D: DrawTarget<Color = Rgb565>,
@@ -24,10 +24,18 @@ pub mod matrix;
 #[cfg(feature = "ledmatrix")]
 pub mod patterns;
 
+#[cfg(feature = "b1display")]
+pub mod dashboard;
+#[cfg(feature = "b1display")]
+pub mod framebuffer;
 #[cfg(feature = "b1display")]
 pub mod graphics;
 #[cfg(feature = "b1display")]
 pub mod lcd_hal;
+#[cfg(feature = "b1display")]
+pub mod rle;
+#[cfg(feature = "b1display")]
+pub mod screensaver;
 
 #[cfg(all(feature = "c1minimal", not(feature = "qtpy")))]
 pub mod minimal_hal;
@@ -35,5 +43,9 @@ pub mod minimal_hal;
 #[cfg(feature = "qtpy")]
 pub mod qtpy_hal;
 
+#[cfg(any(feature = "ledmatrix", feature = "b1display"))]
+pub mod clock;
+
 pub mod control;
+pub mod flash;
 pub mod serialnum;
@@ -1,8 +1,11 @@
 use crate::animations::*;
+use crate::clock::{ClockState, DeviceTime};
 use crate::control::PwmFreqArg;
 use crate::games::game_of_life::GameOfLifeState;
+use crate::games::high_scores::HighScores;
 use crate::games::pong::PongState;
 use crate::games::snake::SnakeState;
+use crate::games::tetris::TetrisState;
 
 pub const WIDTH: usize = 9;
 pub const HEIGHT: usize = 34;
@@ -25,18 +28,37 @@ impl Grid {
 }
 
 pub struct LedmatrixState {
-    /// Currently displayed grid
+    /// Currently displayed grid, the front buffer
     pub grid: Grid,
-    /// Temporary buffer for building a new grid
-    pub col_buffer: Grid,
+    /// Back buffer for building a new grid, column by column
+    /// Shown right away by DrawGreyColBuffer, or on the next frame by Present
+    pub back_buffer: Grid,
+    /// Present the back buffer on the next frame. Whether to respond, once it's shown
+    pub present_pending: Option<bool>,
+    /// Frames rendered since boot, wraps around
+    pub frame: u32,
     /// Whether the grid is currently being animated
     pub animate: bool,
+    /// Rows that the animation scrolled the grid down by
+    /// The grid itself isn't touched, it's only shown shifted.
+    pub scroll_offset: usize,
     /// LED brightness out of 255
     pub brightness: u8,
     /// Current sleep state
     pub sleeping: SleepState,
     /// State of the current game, if any
     pub game: Option<GameState>,
+    /// Whether the game is paused. It doesn't step and only takes controls to resume
+    pub game_paused: bool,
+    /// Whether the game was paused by going to sleep, to resume it when waking up
+    pub game_paused_by_sleep: bool,
+    /// Game step period in microseconds, set by the host. Otherwise each game picks its own
+    pub game_step_period: Option<u64>,
+    /// Best scores of each game, as saved in flash
+    pub high_scores: HighScores,
+    /// Whether the score of the game that's over is the best so far
+    /// None until the game is over and its score has been recorded.
+    pub new_high_score: Option<bool>,
     /// Animation period in microseconds
     pub animation_period: u64,
     /// Current LED PWM frequency
@@ -47,8 +69,22 @@ pub struct LedmatrixState {
     /// - Startup is instant, no animation
     /// - Sleep/wake transition is instant, no animation/fading
     /// - No automatic sleeping
+    /// - Game steps are logged over serial
     pub debug_mode: bool,
     pub upcoming_frames: Option<Animation>,
+    /// Staging buffer for text or images to scroll through
+    pub scroll_strip: ScrollStrip,
+    /// Time of day, once set by the host
+    pub time: Option<DeviceTime>,
+    /// Clock that's shown instead of anything else
+    pub clock: Option<ClockState>,
+}
+
+impl LedmatrixState {
+    /// Brightness of the pixel, as it's shown with the scroll offset applied
+    pub fn shown_pixel(&self, x: usize, y: usize) -> u8 {
+        self.grid.0[x][(y + HEIGHT - self.scroll_offset) % HEIGHT]
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -73,5 +109,6 @@ pub enum SleepReason {
 pub enum GameState {
     Snake(SnakeState),
     Pong(PongState),
+    Tetris(TetrisState),
     GameOfLife(GameOfLifeState),
 }
@@ -1,3 +1,5 @@
+use core::ops::Range;
+
 use rp2040_hal::{
     gpio::{
         bank0::{Gpio26, Gpio27},
@@ -6,6 +8,7 @@ use rp2040_hal::{
     pac::I2C1,
 };
 
+use crate::clock::*;
 use crate::led_hal as bsp;
 use crate::mapping::*;
 use crate::matrix::*;
@@ -291,6 +294,100 @@ pub fn rows(n: usize) -> Grid {
     grid
 }
 
+/// Show the time as HH:MM
+/// In portrait the digits are stacked from top to bottom. In landscape
+/// they're next to each other, to read with the matrix turned sideways.
+pub fn display_clock(hours: u8, minutes: u8, landscape: bool) -> Grid {
+    let mut grid = Grid::default();
+    for (i, glyph) in clock_glyphs(hours, minutes).iter().enumerate() {
+        if landscape {
+            // Centered in 34x9, with a column between the glyphs
+            blit_glyph(&mut grid, glyph, 2 + i * (GLYPH_WIDTH + 1), 1, true);
+        } else {
+            // Centered in 9x34, with a row between the glyphs
+            blit_glyph(&mut grid, glyph, 2, i * (GLYPH_HEIGHT + 1), false);
+        }
+    }
+    grid
+}
+
+/// Draw a glyph with its top left corner at x and y, as seen from the front
+/// The columns of the grid go from right to left, like in `draw`. In landscape
+/// x and y are in the 34x9 view, turned like landscape images from the host.
+fn blit_glyph(grid: &mut Grid, glyph: &[u8; 6], x: usize, y: usize, landscape: bool) {
+    for gy in 0..GLYPH_HEIGHT {
+        for gx in 0..GLYPH_WIDTH {
+            if !glyph_pixel(glyph, gx, gy) {
+                continue;
+            }
+            let (col, row) = if landscape {
+                (y + gy, HEIGHT - 1 - (x + gx))
+            } else {
+                (x + gx, y + gy)
+            };
+            grid.0[WIDTH - 1 - col][row] = 0xFF;
+        }
+    }
+}
+
+/// Score at the end of a game, digits stacked on top of each other and centered
+/// There's only space for 5 digits, higher scores show as 99999.
+pub fn display_score(score: u32) -> Grid {
+    let mut grid = Grid::default();
+    draw_number(&mut grid, score.min(99_999), 0..HEIGHT);
+    grid
+}
+
+/// Scores of both players at the end of a Pong match, each on their side
+/// Separated by a dim line. There's only space for 2 digits per player.
+pub fn display_scores(upper: u8, lower: u8) -> Grid {
+    let mut grid = Grid::default();
+    draw_number(&mut grid, upper.min(99) as u32, 0..HEIGHT / 2 - 1);
+    for x in 0..WIDTH {
+        grid.0[x][HEIGHT / 2 - 1] = 0x20;
+        grid.0[x][HEIGHT / 2] = 0x20;
+    }
+    draw_number(&mut grid, lower.min(99) as u32, HEIGHT / 2 + 1..HEIGHT);
+    grid
+}
+
+/// H and I, in the same style as the digits
+const HI_GLYPHS: [[u8; 6]; 2] = [
+    [0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b11111],
+];
+
+/// HI, shown in between the score at the end of a game that got a new high score
+pub fn display_high_score() -> Grid {
+    let mut grid = Grid::default();
+    let top = (HEIGHT - (HI_GLYPHS.len() * (GLYPH_HEIGHT + 1) - 1)) / 2;
+    for (i, glyph) in HI_GLYPHS.iter().enumerate() {
+        blit_glyph(&mut grid, glyph, 2, top + i * (GLYPH_HEIGHT + 1), false);
+    }
+    grid
+}
+
+/// Digits stacked on top of each other, centered within the rows
+fn draw_number(grid: &mut Grid, number: u32, rows: Range<usize>) {
+    let mut digits = [0; 5];
+    let mut len = 0;
+    let mut rest = number;
+    loop {
+        digits[len] = (rest % 10) as usize;
+        len += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+
+    let top = rows.start + (rows.len() - (len * (GLYPH_HEIGHT + 1) - 1)) / 2;
+    for i in 0..len {
+        let glyph = &DIGIT_GLYPHS[digits[len - 1 - i]];
+        blit_glyph(grid, glyph, 2, top + i * (GLYPH_HEIGHT + 1), false);
+    }
+}
+
 /// Double sided gradient, bright in the middle, dim top and bottom
 pub fn double_gradient() -> Grid {
     let gradient_drop = 1; // Brightness drop between rows
@@ -331,7 +428,7 @@ pub fn fill_grid_pixels(state: &LedmatrixState, matrix: &mut Foo) {
         for x in 0..WIDTH {
             let (register, page) = (matrix.device.calc_pixel)(x as u8, y as u8);
             brightnesses[(page as usize) * 0xB4 + (register as usize)] =
-                ((state.grid.0[x][y] as u64) * (state.brightness as u64)
+                ((state.shown_pixel(x, y) as u64) * (state.brightness as u64)
                     / (BRIGHTNESS_LEVELS as u64)) as u8;
         }
     }
@@ -0,0 +1,89 @@
+//! Run-length encoded framebuffer updates for the B1 display
+//!
+//! The framebuffer is addressed as a stream of bytes, in the same layout that
+//! SetPixelColumn uses: Column by column, 8 pixels per byte from top to bottom.
+use num::FromPrimitive;
+
+/// Encoded bytes that fit into a single command
+/// 64B buffer minus 3B for magic and command and 2B for the offset
+pub const RLE_BYTES: usize = 59;
+/// Bytes per column of 400 pixels
+pub const COLUMN_BYTES: usize = 50;
+
+#[derive(num_derive::FromPrimitive)]
+enum RleOp {
+    /// Followed by the number of bytes and the bytes themselves
+    Literal = 0x00,
+    /// Followed by the number of repetitions and the byte to repeat
+    Repeat = 0x01,
+    /// Followed by the number of bytes (u16) to leave unchanged
+    Skip = 0x02,
+}
+
+/// Decodes the operations of a single command into bytes and their positions
+pub struct RleDecoder<'a> {
+    data: &'a [u8],
+    /// Position in the framebuffer of the next byte
+    offset: usize,
+    /// Bytes left of the current literal or repetition
+    remaining: usize,
+    /// Byte of the current repetition. None while in a literal
+    repeat: Option<u8>,
+}
+
+impl<'a> RleDecoder<'a> {
+    pub fn new(data: &'a [u8], offset: usize) -> Self {
+        RleDecoder {
+            data,
+            offset,
+            remaining: 0,
+            repeat: None,
+        }
+    }
+}
+
+impl Iterator for RleDecoder<'_> {
+    /// Position in the framebuffer and the byte to write there
+    type Item = (usize, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining == 0 {
+            let (op, args) = self.data.split_first()?;
+            match FromPrimitive::from_u8(*op)? {
+                RleOp::Literal => {
+                    let (len, rest) = args.split_first()?;
+                    self.remaining = *len as usize;
+                    self.repeat = None;
+                    self.data = rest;
+                }
+                RleOp::Repeat => {
+                    let [len, byte, rest @ ..] = args else {
+                        return None;
+                    };
+                    self.remaining = *len as usize;
+                    self.repeat = Some(*byte);
+                    self.data = rest;
+                }
+                RleOp::Skip => {
+                    let [lo, hi, rest @ ..] = args else {
+                        return None;
+                    };
+                    self.offset += u16::from_le_bytes([*lo, *hi]) as usize;
+                    self.data = rest;
+                }
+            }
+        }
+
+        let byte = if let Some(byte) = self.repeat {
+            byte
+        } else {
+            let (byte, rest) = self.data.split_first()?;
+            self.data = rest;
+            *byte
+        };
+        let item = (self.offset, byte);
+        self.remaining -= 1;
+        self.offset += 1;
+        Some(item)
+    }
+}
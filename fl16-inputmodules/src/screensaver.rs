@@ -0,0 +1,124 @@
+//! Screensaver modes of the B1 display
+//!
+//! The screensaver draws directly onto the display, not into the framebuffer
+//! copy. When it's turned off, the content from before is restored.
+use embedded_graphics::pixelcolor::Rgb565;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+
+use crate::framebuffer::{FRAMEBUFFER_BYTES, HEIGHT, WIDTH};
+
+/// Bitmap bytes that fit into a single UploadBitmap command
+/// 64B buffer minus 3B for magic and command, 4B for the size and 2B for the offset
+pub const BITMAP_CHUNK_BYTES: usize = 55;
+
+/// What the screensaver shows
+#[derive(Copy, Clone, PartialEq, num_derive::FromPrimitive)]
+pub enum ScreenSaverMode {
+    /// Framework logo, bouncing off the edges
+    Logo = 0x00,
+    /// Bitmap uploaded by the host, bouncing off the edges
+    /// Falls back to the logo, while there's none
+    Bitmap = 0x01,
+    /// Clock in large digits. Blank until the host set the time
+    Clock = 0x02,
+    /// Content from before, moved around by a few pixels once in a while
+    PixelShift = 0x03,
+    /// Nothing, just white
+    Blank = 0x04,
+    /// Content from before, inverted and back once in a while
+    Invert = 0x05,
+}
+
+#[derive(Copy, Clone)]
+pub struct ScreenSaverConfig {
+    pub mode: ScreenSaverMode,
+    /// Seconds to wait after booting or waking up, before the screensaver starts
+    /// Drawing anything in the meantime keeps it off.
+    pub timeout: u16,
+    /// Whether to start the screensaver after booting or waking up at all
+    pub on_wake: bool,
+}
+
+impl Default for ScreenSaverConfig {
+    fn default() -> Self {
+        Self {
+            mode: ScreenSaverMode::Logo,
+            timeout: 0,
+            on_wake: true,
+        }
+    }
+}
+
+/// Starts the screensaver when the content hasn't changed for a while
+#[derive(Copy, Clone)]
+pub struct IdleProtection {
+    /// Seconds without new content, before the screensaver starts. 0 to turn it off
+    pub timeout: u16,
+    /// What to show, independent of the mode of the screensaver
+    pub mode: ScreenSaverMode,
+}
+
+impl Default for IdleProtection {
+    fn default() -> Self {
+        Self {
+            timeout: 10 * 60,
+            mode: ScreenSaverMode::PixelShift,
+        }
+    }
+}
+
+/// Black and white bitmap, row by row, 8 pixels per byte. A set bit is black
+/// Can be up to the size of the screen.
+pub struct Bitmap {
+    size: Size,
+    bytes: [u8; FRAMEBUFFER_BYTES],
+}
+
+impl Default for Bitmap {
+    fn default() -> Self {
+        Bitmap {
+            size: Size::zero(),
+            bytes: [0; FRAMEBUFFER_BYTES],
+        }
+    }
+}
+
+impl Bitmap {
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size.width == 0 || self.size.height == 0
+    }
+
+    /// Whether a bitmap of this size fits onto the screen
+    pub fn fits(size: Size) -> bool {
+        size.width as usize <= WIDTH && size.height as usize <= HEIGHT
+    }
+
+    /// Write part of the bitmap, starting at the byte offset
+    /// A different size than before starts a new, white bitmap.
+    pub fn write(&mut self, size: Size, offset: usize, bytes: &[u8]) {
+        if size != self.size {
+            self.size = size;
+            self.bytes = [0; FRAMEBUFFER_BYTES];
+        }
+        if let Some(rest) = self.bytes.get_mut(offset..) {
+            let len = bytes.len().min(rest.len());
+            rest[..len].copy_from_slice(&bytes[..len]);
+        }
+    }
+
+    pub fn draw<D>(&self, target: &mut D, top_left: Point) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let area = Rectangle::new(top_left, self.size);
+        target.draw_iter(area.points().enumerate().map(|(i, point)| {
+            let black = self.bytes[i / 8] & (1 << (i % 8)) != 0;
+            Pixel(point, if black { Rgb565::BLACK } else { Rgb565::WHITE })
+        }))
+    }
+}
@@ -25,6 +25,78 @@ pub enum PowerMode {
     High,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum ImageFit {
+    /// Scale to fit the screen, leaving white borders
+    Fit,
+    /// Scale to fill the screen, cutting off the edges that don't fit
+    Fill,
+    /// Don't scale, cut off or add white borders in the original size
+    Crop,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum Dither {
+    /// No dithering. Best for images that are black and white already
+    Threshold,
+    /// Error diffusion, best for photos
+    FloydSteinberg,
+    /// Ordered dithering, in a regular pattern
+    Bayer,
+    /// Error diffusion with more contrast than Floyd-Steinberg
+    Atkinson,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum ClockFace {
+    /// Hours and minutes in large digits
+    Digital = 0x01,
+    /// Dial with hour and minute hands
+    Analog = 0x02,
+    /// Digits to read the LED matrix sideways
+    #[value(skip)]
+    Landscape = 0x03,
+}
+
+/// What the screensaver shows
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum ScreenSaverMode {
+    /// Framework logo, bouncing off the edges
+    Logo = 0x00,
+    /// Bitmap uploaded with --screen-saver-bitmap, bouncing off the edges
+    Bitmap = 0x01,
+    /// Clock in large digits, once the time is set
+    Clock = 0x02,
+    /// What was shown before, moved by a few pixels every minute
+    PixelShift = 0x03,
+    /// Nothing, just white
+    Blank = 0x04,
+    /// What was shown before, inverted and back every minute
+    Invert = 0x05,
+}
+
+/// Fonts built into the firmware, by character size in pixels
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum TextFont {
+    #[value(name = "4x6")]
+    Font4x6 = 0x00,
+    #[value(name = "6x10")]
+    Font6x10 = 0x01,
+    #[value(name = "9x15")]
+    Font9x15 = 0x02,
+    #[value(name = "9x18-bold")]
+    Font9x18Bold = 0x03,
+    #[value(name = "10x20")]
+    Font10x20 = 0x04,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum TextAlign {
+    Left = 0x00,
+    Center = 0x01,
+    Right = 0x02,
+}
+
 /// B1 Display
 #[derive(Parser, Debug)]
 #[command(arg_required_else_help = true)]
@@ -63,6 +135,32 @@ pub struct B1DisplaySubcommand {
     #[arg(long)]
     pub screen_saver: Option<Option<bool>>,
 
+    /// Set/get what the screensaver shows
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub screen_saver_mode: Option<Option<ScreenSaverMode>>,
+
+    /// Seconds to wait after waking up, before the screensaver starts
+    #[arg(long)]
+    pub screen_saver_timeout: Option<u16>,
+
+    /// Whether to start the screensaver after waking up at all
+    #[arg(long)]
+    pub screen_saver_on_wake: Option<bool>,
+
+    /// Upload an image for the bitmap screensaver, converted to black&white
+    #[arg(long)]
+    pub screen_saver_bitmap: Option<String>,
+
+    /// Set/get the seconds without new content, before the screensaver starts. 0 to turn off
+    #[arg(long)]
+    pub idle_timeout: Option<Option<u16>>,
+
+    /// What the screensaver shows, when it starts because nothing changed
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub idle_mode: Option<ScreenSaverMode>,
+
     /// Set/get FPS
     #[arg(long)]
     #[clap(value_enum)]
@@ -76,15 +174,80 @@ pub struct B1DisplaySubcommand {
     #[arg(long)]
     pub animation_fps: Option<Option<u16>>,
 
-    /// Display a black&white image (300x400px)
+    /// Display an image, converted to black&white
     #[arg(long)]
     pub image: Option<String>,
 
-    /// Display an animated black&white GIF (300x400px)
+    /// Display an animated GIF, converted to black&white
     #[arg(long)]
     pub animated_gif: Option<String>,
 
+    /// How to scale images and videos that aren't 300x400px [default: fit]
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub fit: Option<ImageFit>,
+
+    /// How to convert images and videos to black&white [default: floyd-steinberg]
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub dither: Option<Dither>,
+
+    /// Play a video in black&white, scaled like --fit says. Formats other than GIF and APNG require ffmpeg
+    #[arg(long)]
+    pub video: Option<String>,
+
+    /// Draw text, rendered by the firmware
+    #[arg(long)]
+    pub text: Option<String>,
+
+    /// Where to draw the text, in pixels from the top left [default: 0 0]
+    #[arg(long, num_args(2), value_names = ["X", "Y"])]
+    pub text_position: Option<Vec<u16>>,
+
+    /// Font to draw the text with [default: 9x15]
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub text_font: Option<TextFont>,
+
+    /// Whether the position is the left edge, center or right edge of the text [default: left]
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub text_align: Option<TextAlign>,
+
+    /// Wrap lines of text that are wider than this many pixels
+    #[arg(long)]
+    pub text_wrap: Option<u16>,
+
+    /// Draw white text on black, instead of black on white
+    #[arg(long)]
+    pub text_inverted: bool,
+
+    /// Fill a rectangle with white before drawing text
+    #[arg(long, num_args(4), value_names = ["X", "Y", "WIDTH", "HEIGHT"])]
+    pub clear_region: Option<Vec<u16>>,
+
+    /// Fill the region with black instead of white
+    #[arg(long)]
+    pub clear_black: bool,
+
+    /// Show a clock, kept by the module. Keeps running to sync it with the computer's time
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub clock: Option<ClockFace>,
+
+    /// Set the module's time to the computer's time
+    #[arg(long)]
+    pub set_time: bool,
+
+    /// Show the time, CPU and memory usage and battery. Keeps updating until stopped
+    #[arg(long)]
+    pub dashboard: bool,
+
     /// Clear display RAM
     #[arg(long)]
     pub clear_ram: bool,
+
+    /// Save what the display shows as an image, without the screensaver
+    #[arg(long)]
+    pub screenshot: Option<String>,
 }
@@ -0,0 +1,180 @@
+//! Dashboard of system stats on the B1 display
+//!
+//! The layout of widgets is sent once. After that, only the data of widgets
+//! that changed is sent and the firmware redraws just those.
+//!
+//! CPU, memory and battery stats are read from /proc and /sys, so they're
+//! only available on Linux.
+use std::collections::HashMap;
+use std::fs;
+
+use chrono::{Local, Timelike};
+
+use crate::b1display::{TextAlign, TextFont};
+
+/// Kinds of widgets the firmware can draw
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WidgetKind {
+    Text = 0x01,
+    Clock = 0x02,
+    Bar = 0x03,
+    Battery = 0x04,
+}
+
+/// Where to put a widget and how it looks
+pub struct Widget {
+    pub id: u8,
+    pub kind: WidgetKind,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub font: TextFont,
+    pub align: TextAlign,
+}
+
+const CLOCK: u8 = 0;
+const DATE: u8 = 1;
+const CPU_LABEL: u8 = 2;
+const CPU_BAR: u8 = 3;
+const MEMORY_LABEL: u8 = 4;
+const MEMORY_BAR: u8 = 5;
+const BATTERY_LABEL: u8 = 6;
+const BATTERY: u8 = 7;
+
+/// Widget with the default font and alignment
+fn widget(id: u8, kind: WidgetKind, [x, y, width, height]: [u16; 4]) -> Widget {
+    Widget {
+        id,
+        kind,
+        x,
+        y,
+        width,
+        height,
+        font: TextFont::Font9x15,
+        align: TextAlign::Left,
+    }
+}
+
+/// Layout of the 300x400px screen
+pub fn layout() -> Vec<Widget> {
+    vec![
+        Widget {
+            font: TextFont::Font10x20,
+            align: TextAlign::Center,
+            ..widget(CLOCK, WidgetKind::Clock, [0, 30, 300, 40])
+        },
+        Widget {
+            align: TextAlign::Center,
+            ..widget(DATE, WidgetKind::Text, [0, 75, 300, 15])
+        },
+        widget(CPU_LABEL, WidgetKind::Text, [20, 130, 260, 15]),
+        widget(CPU_BAR, WidgetKind::Bar, [20, 150, 260, 20]),
+        widget(MEMORY_LABEL, WidgetKind::Text, [20, 190, 260, 15]),
+        widget(MEMORY_BAR, WidgetKind::Bar, [20, 210, 260, 20]),
+        widget(BATTERY_LABEL, WidgetKind::Text, [20, 250, 260, 15]),
+        widget(BATTERY, WidgetKind::Battery, [20, 270, 120, 50]),
+    ]
+}
+
+/// Keeps track of what the widgets show, to only update the ones that changed
+#[derive(Default)]
+pub struct Dashboard {
+    /// CPU time spent busy and in total, at the last update
+    cpu_times: Option<(u64, u64)>,
+    /// Data that was last sent to each widget
+    sent: HashMap<u8, Vec<u8>>,
+}
+
+impl Dashboard {
+    /// Data of the widgets that changed since the last update
+    pub fn changed_widgets(&mut self) -> Vec<(u8, Vec<u8>)> {
+        let now = Local::now();
+        let cpu = self.cpu_usage();
+        let memory = memory_usage();
+        let battery = battery();
+
+        let mut data = vec![
+            (
+                CLOCK,
+                vec![now.hour() as u8, now.minute() as u8, now.second() as u8],
+            ),
+            (DATE, now.format("%a, %d %b %Y").to_string().into_bytes()),
+            (CPU_LABEL, percentage_label("CPU", cpu)),
+            (CPU_BAR, vec![cpu.unwrap_or(0)]),
+            (MEMORY_LABEL, percentage_label("Memory", memory)),
+            (MEMORY_BAR, vec![memory.unwrap_or(0)]),
+        ];
+        if let Some((percent, charging)) = battery {
+            let status = if charging { " (charging)" } else { "" };
+            let label = format!("Battery {percent}%{status}");
+            data.push((BATTERY_LABEL, label.into_bytes()));
+            data.push((BATTERY, vec![percent, charging as u8]));
+        } else {
+            data.push((BATTERY_LABEL, b"Battery n/a".to_vec()));
+            data.push((BATTERY, vec![0, 0]));
+        }
+
+        data.retain(|(id, data)| self.sent.get(id) != Some(data));
+        for (id, data) in &data {
+            self.sent.insert(*id, data.clone());
+        }
+        data
+    }
+
+    /// CPU usage in percent, since the last time it was checked
+    fn cpu_usage(&mut self) -> Option<u8> {
+        let (busy, total) = cpu_times()?;
+        let (prev_busy, prev_total) = self.cpu_times.replace((busy, total))?;
+        let total = total.checked_sub(prev_total).filter(|total| *total > 0)?;
+        Some((busy.saturating_sub(prev_busy) * 100 / total) as u8)
+    }
+}
+
+fn percentage_label(name: &str, percent: Option<u8>) -> Vec<u8> {
+    match percent {
+        Some(percent) => format!("{name} {percent}%"),
+        None => format!("{name} n/a"),
+    }
+    .into_bytes()
+}
+
+/// CPU time spent busy and in total, since boot
+fn cpu_times() -> Option<(u64, u64)> {
+    let stat = fs::read_to_string("/proc/stat").ok()?;
+    // cpu  user nice system idle iowait irq softirq ...
+    let times: Vec<u64> = stat
+        .lines()
+        .next()?
+        .split_whitespace()
+        .skip(1)
+        .filter_map(|time| time.parse().ok())
+        .collect();
+    let idle = times.get(3)? + times.get(4).unwrap_or(&0);
+    let total: u64 = times.iter().sum();
+    Some((total - idle, total))
+}
+
+/// Memory in use in percent
+fn memory_usage() -> Option<u8> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let value = |key: &str| -> Option<u64> {
+        let line = meminfo.lines().find(|line| line.starts_with(key))?;
+        line.split_whitespace().nth(1)?.parse().ok()
+    };
+    let total = value("MemTotal:")?;
+    let available = value("MemAvailable:")?;
+    (total > 0).then(|| ((total - available.min(total)) * 100 / total) as u8)
+}
+
+/// Charge of the first battery in percent and whether it's charging
+fn battery() -> Option<(u8, bool)> {
+    let supplies = fs::read_dir("/sys/class/power_supply").ok()?;
+    let battery = supplies.flatten().map(|entry| entry.path()).find(|path| {
+        fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == "Battery")
+    })?;
+
+    let capacity = fs::read_to_string(battery.join("capacity")).ok()?;
+    let status = fs::read_to_string(battery.join("status")).unwrap_or_default();
+    Some((capacity.trim().parse().ok()?, status.trim() == "Charging"))
+}
@@ -0,0 +1,63 @@
+//! Find the parts of a frame that changed since the previous one
+use image::GrayImage;
+
+/// Pixels of a BlitRect command, 8 per byte
+pub const BLIT_BYTES: usize = 52;
+
+/// Unchanged rows between two changed ones, that still get merged into one rectangle
+/// Every rectangle needs at least one command, so it's cheaper to resend a few
+/// unchanged rows than to start a new one.
+const MAX_GAP: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirtyRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DirtyRect {
+    /// How many rows fit into a single BlitRect command
+    pub fn rows_per_command(&self) -> u32 {
+        (BLIT_BYTES as u32 * 8 / self.width).max(1)
+    }
+
+    /// How many BlitRect commands it takes to send this rectangle
+    pub fn commands(&self) -> u32 {
+        self.height.div_ceil(self.rows_per_command())
+    }
+}
+
+/// Rectangles around the pixels that changed
+/// Each rectangle covers a band of rows and is only as wide as it needs to be.
+pub fn dirty_rects(previous: &GrayImage, next: &GrayImage) -> Vec<DirtyRect> {
+    assert!(previous.dimensions() == next.dimensions());
+
+    let mut rects: Vec<DirtyRect> = vec![];
+    for y in 0..next.height() {
+        let mut changed =
+            (0..next.width()).filter(|x| previous.get_pixel(*x, y) != next.get_pixel(*x, y));
+        let Some(left) = changed.next() else {
+            continue;
+        };
+        let right = changed.next_back().unwrap_or(left);
+
+        match rects.last_mut() {
+            Some(rect) if y - (rect.y + rect.height) < MAX_GAP => {
+                let left = left.min(rect.x);
+                let right = right.max(rect.x + rect.width - 1);
+                rect.x = left;
+                rect.width = right - left + 1;
+                rect.height = y - rect.y + 1;
+            }
+            _ => rects.push(DirtyRect {
+                x: left,
+                y,
+                width: right - left + 1,
+                height: 1,
+            }),
+        }
+    }
+    rects
+}
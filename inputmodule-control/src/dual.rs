@@ -0,0 +1,414 @@
+//! Games that span two LED matrices, run on the computer
+//!
+//! The two modules next to each other form a single 18x34 playfield. The left
+//! one shows the columns 0-8, the right one 9-17. The game steps here and after
+//! every step each module is sent its half as a frame.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use rand::prelude::*;
+
+use crate::inputmodule::{HEIGHT, WIDTH};
+use crate::ledmatrix::{DualGame, Font, PlayDualArgs, ScrollDirection};
+use crate::play::Key;
+use crate::text::{layout, Frame};
+
+/// Width of both modules together
+pub const DUAL_WIDTH: usize = 2 * WIDTH;
+
+/// Both modules together, indexed by x and then y
+pub type Playfield = [[u8; HEIGHT]; DUAL_WIDTH];
+
+pub const HELP: &str =
+    "Arrows: Move, W/S: Left player in Pong, P/Space: Pause, R: Restart, Q/Esc: Quit";
+
+const PADDLE_HEIGHT: usize = 7;
+/// Rows that a paddle moves with each key press
+const PADDLE_STEP: usize = 2;
+
+/// Pong with a paddle on the outer edge of each module
+/// The ball flies from one module over to the other.
+#[derive(Clone)]
+pub struct DualPong {
+    /// Points of the left and the right player
+    pub score: (u8, u8),
+    ball: (usize, usize),
+    direction: (i8, i8),
+    /// Top row of the left and the right paddle
+    paddles: (usize, usize),
+    /// Hits since the last serve, the ball gets faster with each
+    hits: u64,
+    /// Points that win the match. 0 to keep playing forever
+    points_to_win: u8,
+    /// Whether the computer moves the left paddle
+    ai: bool,
+    pub game_over: bool,
+}
+
+impl DualPong {
+    pub fn new(points_to_win: u8, two_players: bool) -> Self {
+        let top = (HEIGHT - PADDLE_HEIGHT) / 2;
+        let mut pong = DualPong {
+            score: (0, 0),
+            ball: (0, 0),
+            direction: (0, 0),
+            paddles: (top, top),
+            hits: 0,
+            points_to_win,
+            ai: !two_players,
+            game_over: false,
+        };
+        pong.serve(thread_rng().gen());
+        pong
+    }
+
+    /// Start again from the middle, towards one of the players
+    fn serve(&mut self, to_right: bool) {
+        let (x, dx) = if to_right {
+            (DUAL_WIDTH / 2, 1)
+        } else {
+            (DUAL_WIDTH / 2 - 1, -1)
+        };
+        self.ball = (x, HEIGHT / 2);
+        self.direction = (dx, thread_rng().gen_range(-1..=1));
+        self.hits = 0;
+    }
+
+    /// Arrows move the right paddle, W and S the left one
+    /// As soon as the left one is moved, the computer stops playing it.
+    fn handle_key(&mut self, key: Key) {
+        match key {
+            Key::Up => self.paddles.1 = self.paddles.1.saturating_sub(PADDLE_STEP),
+            Key::Down => {
+                self.paddles.1 = (self.paddles.1 + PADDLE_STEP).min(HEIGHT - PADDLE_HEIGHT)
+            }
+            Key::SecondUp => {
+                self.ai = false;
+                self.paddles.0 = self.paddles.0.saturating_sub(PADDLE_STEP);
+            }
+            Key::SecondDown => {
+                self.ai = false;
+                self.paddles.0 = (self.paddles.0 + PADDLE_STEP).min(HEIGHT - PADDLE_HEIGHT);
+            }
+            _ => {}
+        }
+    }
+
+    /// Follow the ball, but skip a step now and then, so that it can be beaten
+    fn move_ai(&mut self) {
+        if thread_rng().gen_ratio(1, 4) {
+            return;
+        }
+        let center = self.paddles.0 + PADDLE_HEIGHT / 2;
+        if self.ball.1 < center {
+            self.paddles.0 = self.paddles.0.saturating_sub(1);
+        } else if self.ball.1 > center {
+            self.paddles.0 = (self.paddles.0 + 1).min(HEIGHT - PADDLE_HEIGHT);
+        }
+    }
+
+    fn step(&mut self) {
+        if self.game_over {
+            return;
+        }
+        if self.ai {
+            self.move_ai();
+        }
+
+        let (x, y) = self.ball;
+        let (dx, mut dy) = self.direction;
+        // Bounce off the top and bottom
+        if (y == 0 && dy < 0) || (y == HEIGHT - 1 && dy > 0) {
+            dy = -dy;
+        }
+        let y = (y as i8 + dy) as usize;
+        let next_x = (x as i8 + dx) as usize;
+        self.direction = (dx, dy);
+
+        // The paddles are in the outermost columns
+        let paddle = match next_x {
+            0 => self.paddles.0,
+            x if x == DUAL_WIDTH - 1 => self.paddles.1,
+            _ => {
+                self.ball = (next_x, y);
+                return;
+            }
+        };
+        if (paddle..paddle + PADDLE_HEIGHT).contains(&y) {
+            // The edges of the paddle send the ball off at an angle
+            let dy = match y - paddle {
+                0 | 1 => -1,
+                5 | 6 => 1,
+                // A straight ball goes off at an angle again, so rallies don't go on forever
+                _ if dy == 0 => *[-1, 1].choose(&mut thread_rng()).unwrap(),
+                _ => dy,
+            };
+            self.direction = (-dx, dy);
+            self.ball = (x, y);
+            self.hits += 1;
+            return;
+        }
+
+        // Missed, the other player gets a point and the one who missed gets the ball
+        let missed_left = next_x == 0;
+        let points = if missed_left {
+            &mut self.score.1
+        } else {
+            &mut self.score.0
+        };
+        *points = points.saturating_add(1);
+        if self.points_to_win > 0 && *points >= self.points_to_win {
+            self.game_over = true;
+        }
+        self.serve(!missed_left);
+    }
+
+    /// Faster with every hit, from 100ms down to 40ms
+    fn step_period(&self) -> Duration {
+        Duration::from_millis(100 - 5 * self.hits.min(12))
+    }
+
+    fn draw(&self) -> Playfield {
+        let mut playfield = [[0; HEIGHT]; DUAL_WIDTH];
+        for y in 0..PADDLE_HEIGHT {
+            playfield[0][self.paddles.0 + y] = 0xFF;
+            playfield[DUAL_WIDTH - 1][self.paddles.1 + y] = 0xFF;
+        }
+        playfield[self.ball.0][self.ball.1] = 0xFF;
+        playfield
+    }
+}
+
+/// Snake on the whole playfield, crossing from one module to the other
+#[derive(Clone)]
+pub struct DualSnake {
+    /// Head first
+    body: VecDeque<(usize, usize)>,
+    direction: (i8, i8),
+    /// Direction of the last step. The snake can't turn back onto itself
+    moved: (i8, i8),
+    food: (usize, usize),
+    /// Whether the snake wraps around the edges, instead of hitting them
+    wrap: bool,
+    pub score: u32,
+    pub game_over: bool,
+    /// Whether the snake filled the whole playfield
+    pub won: bool,
+}
+
+impl DualSnake {
+    pub fn new(wrap: bool) -> Self {
+        let y = HEIGHT / 2;
+        let mut snake = DualSnake {
+            body: VecDeque::from([(6, y), (5, y), (4, y)]),
+            direction: (1, 0),
+            moved: (1, 0),
+            food: (0, 0),
+            wrap,
+            score: 0,
+            game_over: false,
+            won: false,
+        };
+        snake.place_food();
+        snake
+    }
+
+    /// Put the food on a random free cell. Returns false, if there's none left
+    fn place_food(&mut self) -> bool {
+        let free: Vec<(usize, usize)> = (0..DUAL_WIDTH)
+            .flat_map(|x| (0..HEIGHT).map(move |y| (x, y)))
+            .filter(|cell| !self.body.contains(cell))
+            .collect();
+        match free.choose(&mut thread_rng()) {
+            Some(cell) => {
+                self.food = *cell;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn handle_key(&mut self, key: Key) {
+        let direction = match key {
+            Key::Up => (0, -1),
+            Key::Down => (0, 1),
+            Key::Left => (-1, 0),
+            Key::Right => (1, 0),
+            _ => return,
+        };
+        if direction != (-self.moved.0, -self.moved.1) {
+            self.direction = direction;
+        }
+    }
+
+    fn step(&mut self) {
+        if self.game_over {
+            return;
+        }
+        let (x, y) = self.body[0];
+        let x = x as i32 + self.direction.0 as i32;
+        let y = y as i32 + self.direction.1 as i32;
+        let outside = !(0..DUAL_WIDTH as i32).contains(&x) || !(0..HEIGHT as i32).contains(&y);
+        if outside && !self.wrap {
+            self.game_over = true;
+            return;
+        }
+        let head = (
+            x.rem_euclid(DUAL_WIDTH as i32) as usize,
+            y.rem_euclid(HEIGHT as i32) as usize,
+        );
+
+        let eats = head == self.food;
+        // The tail moves out of the way, unless the snake grows
+        if !eats {
+            self.body.pop_back();
+        }
+        if self.body.contains(&head) {
+            self.game_over = true;
+            return;
+        }
+        self.body.push_front(head);
+        self.moved = self.direction;
+
+        if eats {
+            self.score += 1;
+            if !self.place_food() {
+                self.won = true;
+                self.game_over = true;
+            }
+        }
+    }
+
+    /// Faster with every food, from 150ms down to 60ms
+    fn step_period(&self) -> Duration {
+        Duration::from_millis(150 - 3 * self.score.min(30) as u64)
+    }
+
+    fn draw(&self) -> Playfield {
+        let mut playfield = [[0; HEIGHT]; DUAL_WIDTH];
+        let (x, y) = self.food;
+        playfield[x][y] = 0x80;
+        for &(x, y) in &self.body {
+            playfield[x][y] = 0xFF;
+        }
+        playfield
+    }
+}
+
+#[derive(Clone)]
+pub enum DualState {
+    Pong(DualPong),
+    Snake(DualSnake),
+}
+
+impl DualState {
+    pub fn new(args: &PlayDualArgs) -> Self {
+        match args.game {
+            DualGame::Pong => DualState::Pong(DualPong::new(
+                args.points_to_win.unwrap_or(0),
+                args.two_players,
+            )),
+            DualGame::Snake => DualState::Snake(DualSnake::new(args.wrap)),
+        }
+    }
+
+    /// Same game from the start
+    pub fn restarted(&self) -> Self {
+        match self {
+            DualState::Pong(pong) => DualState::Pong(DualPong::new(pong.points_to_win, !pong.ai)),
+            DualState::Snake(snake) => DualState::Snake(DualSnake::new(snake.wrap)),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key) {
+        match self {
+            DualState::Pong(pong) => pong.handle_key(key),
+            DualState::Snake(snake) => snake.handle_key(key),
+        }
+    }
+
+    pub fn step(&mut self) {
+        match self {
+            DualState::Pong(pong) => pong.step(),
+            DualState::Snake(snake) => snake.step(),
+        }
+    }
+
+    pub fn step_period(&self) -> Duration {
+        match self {
+            DualState::Pong(pong) => pong.step_period(),
+            DualState::Snake(snake) => snake.step_period(),
+        }
+    }
+
+    /// Frames of the left and the right module
+    /// Once the game is over, they show the score instead.
+    pub fn frames(&self) -> (Frame, Frame) {
+        match self {
+            DualState::Pong(pong) if pong.game_over => (
+                number_frame(pong.score.0 as u32),
+                number_frame(pong.score.1 as u32),
+            ),
+            DualState::Snake(snake) if snake.game_over => {
+                (number_frame(snake.score), number_frame(snake.score))
+            }
+            DualState::Pong(pong) => split(&pong.draw()),
+            DualState::Snake(snake) => split(&snake.draw()),
+        }
+    }
+
+    /// Score and status, to show in the terminal
+    pub fn summary(&self, paused: bool) -> String {
+        let (score, game_over, won) = match self {
+            DualState::Pong(pong) => (
+                format!("Pong {} - {}", pong.score.0, pong.score.1),
+                pong.game_over,
+                false,
+            ),
+            DualState::Snake(snake) => (
+                format!("Snake Score: {}", snake.score),
+                snake.game_over,
+                snake.won,
+            ),
+        };
+        let status = if won {
+            " (Won)"
+        } else if game_over {
+            " (Game over)"
+        } else if paused {
+            " (Paused)"
+        } else {
+            ""
+        };
+        format!("{score}{status}")
+    }
+}
+
+/// Split the playfield into the frames of the left and the right module
+pub fn split(playfield: &Playfield) -> (Frame, Frame) {
+    let mut left = [[0; HEIGHT]; WIDTH];
+    let mut right = [[0; HEIGHT]; WIDTH];
+    left.copy_from_slice(&playfield[..WIDTH]);
+    right.copy_from_slice(&playfield[WIDTH..]);
+    (left, right)
+}
+
+/// Number with the digits stacked on top of each other, centered
+fn number_frame(number: u32) -> Frame {
+    let mut rows = layout(
+        &number.to_string(),
+        Font::Standard,
+        ScrollDirection::Vertical,
+    );
+    // No need for a gap after the last digit
+    rows.pop();
+
+    let mut frame = [[0; HEIGHT]; WIDTH];
+    let y_offset = HEIGHT.saturating_sub(rows.len()) / 2;
+    for (y, row) in rows.iter().take(HEIGHT).enumerate() {
+        for x in 0..WIDTH {
+            frame[x][y_offset + y] = row[x];
+        }
+    }
+    frame
+}
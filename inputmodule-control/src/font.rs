@@ -553,3 +553,369 @@ pub fn convert_font(c: char) -> Vec<u8> {
         _ => convert_font('?'),
     }
 }
+
+/// 3x5 font. Narrow enough to fit two characters side by side, or eight
+/// characters along the long side of the matrix.
+#[rustfmt::skip]
+pub fn convert_small_font(c: char) -> Vec<u8> {
+    match c {
+        '0' => vec![
+            1, 1, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 1,
+        ],
+        '1' => vec![
+            0, 1, 0,
+            1, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+            1, 1, 1,
+        ],
+        '2' => vec![
+            1, 1, 1,
+            0, 0, 1,
+            1, 1, 1,
+            1, 0, 0,
+            1, 1, 1,
+        ],
+        '3' => vec![
+            1, 1, 1,
+            0, 0, 1,
+            1, 1, 1,
+            0, 0, 1,
+            1, 1, 1,
+        ],
+        '4' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 1,
+            0, 0, 1,
+            0, 0, 1,
+        ],
+        '5' => vec![
+            1, 1, 1,
+            1, 0, 0,
+            1, 1, 1,
+            0, 0, 1,
+            1, 1, 1,
+        ],
+        '6' => vec![
+            1, 1, 1,
+            1, 0, 0,
+            1, 1, 1,
+            1, 0, 1,
+            1, 1, 1,
+        ],
+        '7' => vec![
+            1, 1, 1,
+            0, 0, 1,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+        ],
+        '8' => vec![
+            1, 1, 1,
+            1, 0, 1,
+            1, 1, 1,
+            1, 0, 1,
+            1, 1, 1,
+        ],
+        '9' => vec![
+            1, 1, 1,
+            1, 0, 1,
+            1, 1, 1,
+            0, 0, 1,
+            1, 1, 1,
+        ],
+        ':' => vec![
+            0, 0, 0,
+            0, 1, 0,
+            0, 0, 0,
+            0, 1, 0,
+            0, 0, 0,
+        ],
+        ' ' => vec![
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+        ],
+        '?' => vec![
+            1, 1, 0,
+            0, 0, 1,
+            0, 1, 0,
+            0, 0, 0,
+            0, 1, 0,
+        ],
+        '.' => vec![
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+            0, 1, 0,
+        ],
+        ',' => vec![
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+            0, 1, 0,
+            1, 0, 0,
+        ],
+        '!' => vec![
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+            0, 0, 0,
+            0, 1, 0,
+        ],
+        '/' => vec![
+            0, 0, 1,
+            0, 0, 1,
+            0, 1, 0,
+            1, 0, 0,
+            1, 0, 0,
+        ],
+        '*' => vec![
+            0, 0, 0,
+            1, 0, 1,
+            0, 1, 0,
+            1, 0, 1,
+            0, 0, 0,
+        ],
+        '%' => vec![
+            1, 0, 1,
+            0, 0, 1,
+            0, 1, 0,
+            1, 0, 0,
+            1, 0, 1,
+        ],
+        '+' => vec![
+            0, 0, 0,
+            0, 1, 0,
+            1, 1, 1,
+            0, 1, 0,
+            0, 0, 0,
+        ],
+        '-' => vec![
+            0, 0, 0,
+            0, 0, 0,
+            1, 1, 1,
+            0, 0, 0,
+            0, 0, 0,
+        ],
+        '=' => vec![
+            0, 0, 0,
+            1, 1, 1,
+            0, 0, 0,
+            1, 1, 1,
+            0, 0, 0,
+        ],
+        '\'' => vec![
+            0, 1, 0,
+            0, 1, 0,
+            0, 0, 0,
+            0, 0, 0,
+            0, 0, 0,
+        ],
+        '(' => vec![
+            0, 0, 1,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+            0, 0, 1,
+        ],
+        ')' => vec![
+            1, 0, 0,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+            1, 0, 0,
+        ],
+        'A' => vec![
+            0, 1, 0,
+            1, 0, 1,
+            1, 1, 1,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'B' => vec![
+            1, 1, 0,
+            1, 0, 1,
+            1, 1, 0,
+            1, 0, 1,
+            1, 1, 0,
+        ],
+        'C' => vec![
+            0, 1, 1,
+            1, 0, 0,
+            1, 0, 0,
+            1, 0, 0,
+            0, 1, 1,
+        ],
+        'D' => vec![
+            1, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 0,
+        ],
+        'E' => vec![
+            1, 1, 1,
+            1, 0, 0,
+            1, 1, 0,
+            1, 0, 0,
+            1, 1, 1,
+        ],
+        'F' => vec![
+            1, 1, 1,
+            1, 0, 0,
+            1, 1, 0,
+            1, 0, 0,
+            1, 0, 0,
+        ],
+        'G' => vec![
+            0, 1, 1,
+            1, 0, 0,
+            1, 0, 1,
+            1, 0, 1,
+            0, 1, 1,
+        ],
+        'H' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 1,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'I' => vec![
+            1, 1, 1,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+            1, 1, 1,
+        ],
+        'J' => vec![
+            0, 0, 1,
+            0, 0, 1,
+            0, 0, 1,
+            1, 0, 1,
+            0, 1, 0,
+        ],
+        'K' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'L' => vec![
+            1, 0, 0,
+            1, 0, 0,
+            1, 0, 0,
+            1, 0, 0,
+            1, 1, 1,
+        ],
+        'M' => vec![
+            1, 0, 1,
+            1, 1, 1,
+            1, 1, 1,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'N' => vec![
+            1, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'O' => vec![
+            0, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            0, 1, 0,
+        ],
+        'P' => vec![
+            1, 1, 0,
+            1, 0, 1,
+            1, 1, 0,
+            1, 0, 0,
+            1, 0, 0,
+        ],
+        'Q' => vec![
+            0, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 0,
+            0, 1, 1,
+        ],
+        'R' => vec![
+            1, 1, 0,
+            1, 0, 1,
+            1, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'S' => vec![
+            0, 1, 1,
+            1, 0, 0,
+            0, 1, 0,
+            0, 0, 1,
+            1, 1, 0,
+        ],
+        'T' => vec![
+            1, 1, 1,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+        ],
+        'U' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 1,
+        ],
+        'V' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            1, 0, 1,
+            0, 1, 0,
+        ],
+        'W' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            1, 1, 1,
+            1, 1, 1,
+            1, 0, 1,
+        ],
+        'X' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            0, 1, 0,
+            1, 0, 1,
+            1, 0, 1,
+        ],
+        'Y' => vec![
+            1, 0, 1,
+            1, 0, 1,
+            0, 1, 0,
+            0, 1, 0,
+            0, 1, 0,
+        ],
+        'Z' => vec![
+            1, 1, 1,
+            0, 0, 1,
+            0, 1, 0,
+            1, 0, 0,
+            1, 1, 1,
+        ],
+        _ => convert_small_font('?'),
+    }
+}
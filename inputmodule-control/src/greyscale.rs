@@ -0,0 +1,73 @@
+//! Convert images to greyscale for the LED matrix
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage};
+
+use crate::inputmodule::{HEIGHT, WIDTH};
+
+/// How to turn an image into LED brightness values
+#[derive(Copy, Clone, Debug)]
+pub struct GreyscaleOptions {
+    pub filter: FilterType,
+    /// Gamma of the brightness curve. 1.0 is linear
+    pub gamma: f32,
+    /// Stretch the brightness to use the full range
+    pub auto_contrast: bool,
+}
+
+/// Scale the image to the size of the matrix, cutting off what doesn't fit
+/// In landscape the image is scaled to 34x9 and rotated to fit the display.
+pub fn scale_matrix_image(img: &DynamicImage, landscape: bool, filter: FilterType) -> GrayImage {
+    if landscape {
+        let img = img.resize_to_fill(HEIGHT as u32, WIDTH as u32, filter);
+        // Same orientation as the sideways LOTUS pattern
+        imageops::rotate270(&img.into_luma8())
+    } else {
+        img.resize_to_fill(WIDTH as u32, HEIGHT as u32, filter)
+            .into_luma8()
+    }
+}
+
+/// Stretch the brightness, so that the darkest pixel is black and the brightest white
+pub fn auto_contrast(img: &mut GrayImage) {
+    let darkest = img.pixels().map(|pixel| pixel.0[0]).min().unwrap_or(0x00);
+    let brightest = img.pixels().map(|pixel| pixel.0[0]).max().unwrap_or(0xFF);
+    if brightest == darkest {
+        return;
+    }
+
+    let range = (brightest - darkest) as u32;
+    for pixel in img.pixels_mut() {
+        pixel.0[0] = ((pixel.0[0] - darkest) as u32 * 0xFF / range) as u8;
+    }
+}
+
+/// Map perceived brightness to LED brightness
+///
+/// The LED controller's PWM duty cycle, and with it the light output, is linear
+/// to the brightness value. But the eye is much more sensitive to changes in
+/// dark values than in bright ones. Without correction, anything but the
+/// darkest shades looks almost fully bright.
+pub fn apply_gamma(img: &mut GrayImage, gamma: f32) {
+    let mut table = [0u8; 256];
+    for (i, brightness) in table.iter_mut().enumerate() {
+        *brightness = ((i as f32 / 255.0).powf(gamma) * 255.0).round() as u8;
+    }
+
+    for pixel in img.pixels_mut() {
+        pixel.0[0] = table[pixel.0[0] as usize];
+    }
+}
+
+/// Scale and convert an image to the brightness values for the matrix
+pub fn convert_matrix_image(
+    img: &DynamicImage,
+    landscape: bool,
+    options: &GreyscaleOptions,
+) -> GrayImage {
+    let mut img = scale_matrix_image(img, landscape, options.filter);
+    if options.auto_contrast {
+        auto_contrast(&mut img);
+    }
+    apply_gamma(&mut img, options.gamma);
+    img
+}
@@ -1,17 +1,30 @@
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use chrono::Local;
-use image::codecs::gif::GifDecoder;
-use image::{io::Reader as ImageReader, Luma};
-use image::{AnimationDecoder, DynamicImage, ImageBuffer};
+use chrono::{Local, Timelike};
+use image::{imageops, io::Reader as ImageReader, GrayImage, Luma};
+use image::{DynamicImage, ImageBuffer};
 use rand::prelude::*;
 use serialport::{SerialPort, SerialPortInfo, SerialPortType};
 
-use crate::b1display::{B1Pattern, Fps, PowerMode};
+use crate::b1display::{
+    B1Pattern, ClockFace, Dither, Fps, ImageFit, PowerMode, ScreenSaverMode, TextAlign, TextFont,
+};
 use crate::c1minimal::Color;
+use crate::dashboard::{self, Dashboard, Widget};
+use crate::dirty::{dirty_rects, DirtyRect, BLIT_BYTES};
+use crate::dual::{self, DualState};
 use crate::font::{convert_font, convert_symbol};
-use crate::ledmatrix::{Game, GameOfLifeStartParam, Pattern};
+use crate::greyscale::{convert_matrix_image, scale_matrix_image, GreyscaleOptions};
+use crate::ledmatrix::{
+    Font, Game, GameArgs, GameOfLifeStartParam, LedMatrixCommand, LifeRules, Pattern, PlayDualArgs,
+    ResizeFilter, ScrollDirection,
+};
+use crate::monochrome::{dither_image, fit_image, B1_HEIGHT, B1_WIDTH};
+use crate::play::{self, Key, RawTerminal};
+use crate::rle::{encode_rle, pack_framebuffer, unpack_framebuffer};
+use crate::text::{landscape_text, rotate_landscape, scroll_frame, scroll_strip};
+use crate::video::{animation_frames, decode_animation, decode_video, VideoFrame};
 
 const FWK_MAGIC: &[u8] = &[0x32, 0xAC];
 pub const FRAMEWORK_VID: u16 = 0x32AC;
@@ -36,7 +49,7 @@ enum Command {
     _B1Reserved = 0x09,
     StartGame = 0x10,
     GameControl = 0x11,
-    _GameStatus = 0x12,
+    GameStatus = 0x12,
     SetColor = 0x13,
     DisplayOn = 0x14,
     InvertScreen = 0x15,
@@ -50,23 +63,68 @@ enum Command {
     PwmFreq = 0x1E,
     DebugMode = 0x1F,
     Version = 0x20,
+    StageScrollRows = 0x21,
+    StartScroll = 0x22,
+    BlitRect = 0x23,
+    RleFramebuffer = 0x24,
+    DrawText = 0x25,
+    ClearRegion = 0x26,
+    SetWidget = 0x27,
+    UpdateWidget = 0x28,
+    SetTime = 0x29,
+    ClockMode = 0x2A,
+    ScreenSaverMode = 0x2B,
+    UploadBitmap = 0x2C,
+    IdleProtection = 0x2D,
+    GetFramebuffer = 0x2E,
+    GetGrid = 0x2F,
+    Present = 0x30,
+    GameSpeed = 0x31,
+    HighScores = 0x32,
 }
 
 enum GameControlArg {
-    _Up = 0,
-    _Down = 1,
-    _Left = 2,
-    _Right = 3,
+    Up = 0,
+    Down = 1,
+    Left = 2,
+    Right = 3,
     Exit = 4,
-    _SecondLeft = 5,
-    _SecondRight = 6,
+    SecondLeft = 5,
+    SecondRight = 6,
+    Pause = 7,
+    Resume = 8,
+    Restart = 9,
 }
 
-const WIDTH: usize = 9;
-const HEIGHT: usize = 34;
+pub const WIDTH: usize = 9;
+pub const HEIGHT: usize = 34;
+
+/// Maximum number of rows the firmware can scroll through
+const SCROLL_STRIP_ROWS: usize = 512;
+/// Number of rows that fit into a single StageScrollRows command
+const SCROLL_ROWS_PER_COMMAND: usize = 29;
+
+/// Bytes of UTF-8 text that fit into a single DrawText command
+const TEXT_BYTES: usize = 52;
+
+/// Bitmap bytes that fit into a single UploadBitmap command
+const BITMAP_CHUNK_BYTES: usize = 55;
+
+/// Pixels per LED in a screenshot of the LED matrix
+const SCREENSHOT_SCALE: u32 = 10;
+
+/// How often to set the time again, so that the module's clock doesn't drift
+const CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 const SERIAL_TIMEOUT: Duration = Duration::from_millis(20);
 
+/// How long to wait for the module to show a frame, a lot longer than a frame at the lowest FPS
+const PRESENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long to wait for a response while the module might be saving the high scores.
+/// Erasing the flash blocks it for up to a few hundred ms.
+const FLASH_TIMEOUT: Duration = Duration::from_secs(1);
+
 fn match_serialdevs(
     ports: &[SerialPortInfo],
     requested: &Option<String>,
@@ -171,6 +229,22 @@ pub fn serial_commands(args: &crate::ClapCli) {
     match &args.command {
         // TODO: Handle generic commands without code deduplication
         Some(crate::Commands::LedMatrix(ledmatrix_args)) => {
+            let landscape = ledmatrix_args.landscape;
+            // Landscape has room for a whole word in the small font
+            let landscape_font = ledmatrix_args.font.unwrap_or(Font::Small);
+            let scroll_direction = ledmatrix_args.scroll_direction.unwrap_or(if landscape {
+                ScrollDirection::Horizontal
+            } else {
+                ScrollDirection::Vertical
+            });
+            let greyscale = GreyscaleOptions {
+                filter: ledmatrix_args
+                    .resize_filter
+                    .unwrap_or(ResizeFilter::Triangle)
+                    .into(),
+                gamma: ledmatrix_args.gamma,
+                auto_contrast: ledmatrix_args.auto_contrast,
+            };
             for serialdev in &serialdevs {
                 if args.verbose {
                     println!("Selected serialdev: {:?}", serialdev);
@@ -202,11 +276,13 @@ pub fn serial_commands(args: &crate::ClapCli) {
                     simple_cmd(serialdev, Command::Panic, &[0x00]);
                 }
                 if let Some(image_path) = &ledmatrix_args.image_bw {
-                    display_bw_image_cmd(serialdev, image_path);
+                    display_bw_image_cmd(serialdev, image_path, landscape, &greyscale);
                 }
 
                 if let Some(image_path) = &ledmatrix_args.image_gray {
-                    display_gray_image_cmd(serialdev, image_path);
+                    if !is_animated(image_path) {
+                        display_gray_image_cmd(serialdev, image_path, landscape, &greyscale);
+                    }
                 }
 
                 if let Some(values) = &ledmatrix_args.eq {
@@ -214,15 +290,35 @@ pub fn serial_commands(args: &crate::ClapCli) {
                 }
 
                 if let Some(s) = &ledmatrix_args.string {
-                    show_string(serialdev, s);
+                    if landscape {
+                        show_landscape_string(serialdev, s, landscape_font);
+                    } else {
+                        show_string(serialdev, s);
+                    }
                 }
 
                 if let Some(symbols) = &ledmatrix_args.symbols {
-                    show_symbols(serialdev, symbols);
+                    if landscape {
+                        show_landscape_symbols(serialdev, symbols, landscape_font);
+                    } else {
+                        show_symbols(serialdev, symbols);
+                    }
+                }
+
+                if let Some(text) = &ledmatrix_args.scroll {
+                    if ledmatrix_args.scroll_on_device {
+                        scroll_on_device_cmd(
+                            serialdev,
+                            text,
+                            ledmatrix_args.font.unwrap_or(Font::Standard),
+                            scroll_direction,
+                            ledmatrix_args.scroll_speed,
+                        );
+                    }
                 }
 
                 if let Some(game) = ledmatrix_args.start_game {
-                    start_game_cmd(serialdev, game, ledmatrix_args.game_param);
+                    start_game_cmd(serialdev, game, &ledmatrix_args.game_args, &greyscale);
                 }
 
                 if let Some(fps) = ledmatrix_args.animation_fps {
@@ -243,11 +339,58 @@ pub fn serial_commands(args: &crate::ClapCli) {
                         &[GameControlArg::Exit as u8],
                     );
                 }
+                if ledmatrix_args.pause_game {
+                    simple_cmd(
+                        serialdev,
+                        Command::GameControl,
+                        &[GameControlArg::Pause as u8],
+                    );
+                }
+                if ledmatrix_args.resume_game {
+                    simple_cmd(
+                        serialdev,
+                        Command::GameControl,
+                        &[GameControlArg::Resume as u8],
+                    );
+                }
+                if ledmatrix_args.restart_game {
+                    simple_cmd(
+                        serialdev,
+                        Command::GameControl,
+                        &[GameControlArg::Restart as u8],
+                    );
+                }
+                if ledmatrix_args.game_status {
+                    game_status_cmd(serialdev);
+                }
+                if let Some(period) = ledmatrix_args.game_speed {
+                    game_speed_cmd(serialdev, period);
+                }
+                if ledmatrix_args.reset_high_scores {
+                    reset_high_scores_cmd(serialdev);
+                }
+                if ledmatrix_args.high_scores {
+                    high_scores_cmd(serialdev);
+                }
                 if ledmatrix_args.version {
                     get_device_version(serialdev);
                 }
+                if let Some(path) = &ledmatrix_args.screenshot {
+                    ledmatrix_screenshot_cmd(serialdev, path);
+                }
             }
             // Commands that block and need manual looping
+            if let Some(LedMatrixCommand::Play(play_args)) = &ledmatrix_args.command {
+                play_cmd(
+                    &serialdevs[0],
+                    play_args.game,
+                    &play_args.game_args,
+                    &greyscale,
+                );
+            }
+            if let Some(LedMatrixCommand::PlayDual(play_args)) = &ledmatrix_args.command {
+                play_dual_cmd(&serialdevs, play_args, ledmatrix_args.vsync);
+            }
             if ledmatrix_args.blinking {
                 blinking_cmd(&serialdevs);
             }
@@ -264,11 +407,57 @@ pub fn serial_commands(args: &crate::ClapCli) {
                 input_eq_cmd(&serialdevs);
             }
 
+            if ledmatrix_args.set_time {
+                set_time_cmd(&serialdevs);
+            }
             if ledmatrix_args.clock {
-                clock_cmd(&serialdevs);
+                let face = if landscape {
+                    ClockFace::Landscape
+                } else {
+                    ClockFace::Digital
+                };
+                clock_cmd(&serialdevs, face);
+            }
+
+            if let Some(text) = &ledmatrix_args.scroll {
+                if !ledmatrix_args.scroll_on_device {
+                    scroll_cmd(
+                        &serialdevs,
+                        text,
+                        ledmatrix_args.font.unwrap_or(Font::Standard),
+                        scroll_direction,
+                        ledmatrix_args.scroll_speed,
+                        ledmatrix_args.scroll_smooth,
+                        ledmatrix_args.vsync,
+                    );
+                }
+            }
+
+            if let Some(image_path) = &ledmatrix_args.image_gray {
+                if is_animated(image_path) {
+                    animated_gray_image_cmd(
+                        &serialdevs,
+                        image_path,
+                        landscape,
+                        &greyscale,
+                        ledmatrix_args.vsync,
+                    );
+                }
+            }
+
+            if let Some(video_path) = &ledmatrix_args.video {
+                video_cmd(
+                    &serialdevs,
+                    video_path,
+                    landscape,
+                    &greyscale,
+                    ledmatrix_args.vsync,
+                );
             }
         }
         Some(crate::Commands::B1Display(b1display_args)) => {
+            let fit = b1display_args.fit.unwrap_or(ImageFit::Fit);
+            let dither = b1display_args.dither.unwrap_or(Dither::FloydSteinberg);
             for serialdev in &serialdevs {
                 if args.verbose {
                     println!("Selected serialdev: {:?}", serialdev);
@@ -292,6 +481,27 @@ pub fn serial_commands(args: &crate::ClapCli) {
                 if let Some(invert_screen) = b1display_args.invert_screen {
                     invert_screen_cmd(serialdev, invert_screen);
                 }
+                if let Some(image_path) = &b1display_args.screen_saver_bitmap {
+                    screensaver_bitmap_cmd(serialdev, image_path, dither);
+                }
+                if b1display_args.screen_saver_mode.is_some()
+                    || b1display_args.screen_saver_timeout.is_some()
+                    || b1display_args.screen_saver_on_wake.is_some()
+                {
+                    screensaver_mode_cmd(
+                        serialdev,
+                        b1display_args.screen_saver_mode.flatten(),
+                        b1display_args.screen_saver_timeout,
+                        b1display_args.screen_saver_on_wake,
+                    );
+                }
+                if b1display_args.idle_timeout.is_some() || b1display_args.idle_mode.is_some() {
+                    idle_protection_cmd(
+                        serialdev,
+                        b1display_args.idle_timeout.flatten(),
+                        b1display_args.idle_mode,
+                    );
+                }
                 if let Some(screensaver_on) = b1display_args.screen_saver {
                     screensaver_cmd(serialdev, screensaver_on);
                 }
@@ -305,10 +515,28 @@ pub fn serial_commands(args: &crate::ClapCli) {
                     animation_fps_cmd(serialdev, fps);
                 }
                 if let Some(image_path) = &b1display_args.image {
-                    b1display_bw_image_cmd(serialdev, image_path);
+                    b1display_bw_image_cmd(serialdev, image_path, fit, dither);
                 }
                 if let Some(image_path) = &b1display_args.animated_gif {
-                    gif_cmd(serialdev, image_path);
+                    gif_cmd(serialdev, image_path, fit, dither);
+                }
+                if let Some(video_path) = &b1display_args.video {
+                    b1display_video_cmd(serialdev, video_path, fit, dither);
+                }
+                if let Some(region) = &b1display_args.clear_region {
+                    clear_region_cmd(serialdev, region, b1display_args.clear_black);
+                }
+                if let Some(text) = &b1display_args.text {
+                    let position = b1display_args.text_position.as_deref().unwrap_or(&[0, 0]);
+                    let layout = TextLayout {
+                        x: position[0],
+                        y: position[1],
+                        font: b1display_args.text_font.unwrap_or(TextFont::Font9x15),
+                        align: b1display_args.text_align.unwrap_or(TextAlign::Left),
+                        wrap_width: b1display_args.text_wrap.unwrap_or(0),
+                        inverted: b1display_args.text_inverted,
+                    };
+                    draw_text_cmd(serialdev, text, &layout);
                 }
                 if b1display_args.clear_ram {
                     simple_cmd(serialdev, Command::ClearRam, &[0x00]);
@@ -316,6 +544,18 @@ pub fn serial_commands(args: &crate::ClapCli) {
                 if let Some(pattern) = b1display_args.pattern {
                     b1_display_pattern(serialdev, pattern);
                 }
+                if let Some(path) = &b1display_args.screenshot {
+                    b1display_screenshot_cmd(serialdev, path);
+                }
+            }
+            if b1display_args.set_time {
+                set_time_cmd(&serialdevs);
+            }
+            if let Some(face) = b1display_args.clock {
+                clock_cmd(&serialdevs, face);
+            }
+            if b1display_args.dashboard {
+                dashboard_cmd(&serialdevs);
             }
         }
         Some(crate::Commands::C1Minimal(c1minimal_args)) => {
@@ -384,15 +624,363 @@ fn pattern_cmd(serialdev: &str, arg: Pattern) {
     simple_cmd(serialdev, Command::Pattern, &[arg as u8]);
 }
 
-fn start_game_cmd(serialdev: &str, game: Game, param: Option<GameOfLifeStartParam>) {
-    match (game, param) {
-        (Game::GameOfLife, Some(param)) => {
-            simple_cmd(serialdev, Command::StartGame, &[game as u8, param as u8])
+/// Arguments of the StartGame command. None if some are missing
+fn start_game_args(
+    game: Game,
+    game_args: &GameArgs,
+    greyscale: &GreyscaleOptions,
+) -> Option<Vec<u8>> {
+    match (game, game_args.game_param) {
+        (Game::GameOfLife, param) => {
+            let param = match (&game_args.seed_image, param) {
+                (Some(_), _) => GameOfLifeStartParam::Bitmap,
+                (None, Some(param)) => param,
+                (None, None) => {
+                    println!("To start Game of Life, provide a --game-param or --seed-image");
+                    return None;
+                }
+            };
+            let flags = game_args.dead_borders as u8
+                | (game_args.auto_restart as u8) << 1
+                | (game_args.show_age as u8) << 2;
+            // No rules at all means the default ones
+            let rules = game_args.rules.unwrap_or(LifeRules {
+                birth: 0,
+                survive: 0,
+            });
+            let mut args = vec![game as u8, param as u8, flags];
+            args.extend_from_slice(&rules.birth.to_le_bytes());
+            args.extend_from_slice(&rules.survive.to_le_bytes());
+            if let Some(image_path) = &game_args.seed_image {
+                let img = load_bw_matrix_image(image_path, false, greyscale.filter);
+                args.extend_from_slice(&bw_image_bytes(&img));
+            }
+            Some(args)
+        }
+        (Game::Snake, _) => Some(vec![
+            game as u8,
+            game_args.wrap as u8,
+            game_args.snake_speed.unwrap_or(0),
+            game_args.speed_up.unwrap_or(0),
+            game_args.obstacles.unwrap_or(0),
+        ]),
+        (Game::Pong, _) => {
+            let players = if game_args.two_players { 2 } else { 1 };
+            let points_to_win = game_args.points_to_win.unwrap_or(0);
+            Some(vec![game as u8, points_to_win, players])
+        }
+        (_, _) => Some(vec![game as u8]),
+    }
+}
+
+fn start_game_cmd(serialdev: &str, game: Game, game_args: &GameArgs, greyscale: &GreyscaleOptions) {
+    if let Some(args) = start_game_args(game, game_args, greyscale) {
+        simple_cmd(serialdev, Command::StartGame, &args);
+    }
+}
+
+/// What the firmware reports about the running game
+struct GameStatus {
+    game: Option<Game>,
+    paused: bool,
+    game_over: bool,
+    /// Only in Snake, when the board is full
+    won: bool,
+    /// In Pong the upper player's
+    score: u32,
+    /// Only in Pong, the lower player's
+    second_score: u32,
+    /// Level in Tetris, speed in Pong and Snake
+    level: u8,
+    snake_length: u16,
+    lines: u16,
+}
+
+fn get_game_status(port: &mut Box<dyn SerialPort>) -> GameStatus {
+    simple_cmd_port(port, Command::GameStatus, &[]);
+    // The game saves the high scores when it ends
+    port.set_timeout(FLASH_TIMEOUT).unwrap();
+    let mut response: Vec<u8> = vec![0; 32];
+    port.read_exact(response.as_mut_slice())
+        .expect("Found no data!");
+    port.set_timeout(SERIAL_TIMEOUT).unwrap();
+
+    let u32_at = |i: usize| u32::from_le_bytes(response[i..i + 4].try_into().unwrap());
+    let u16_at = |i: usize| u16::from_le_bytes([response[i], response[i + 1]]);
+    GameStatus {
+        game: match response[0] {
+            0 => Some(Game::Snake),
+            1 => Some(Game::Pong),
+            2 => Some(Game::Tetris),
+            3 => Some(Game::GameOfLife),
+            _ => None,
+        },
+        paused: response[1] == 1,
+        game_over: response[1] == 2 || response[1] == 3,
+        won: response[1] == 3,
+        score: u32_at(2),
+        second_score: u32_at(6),
+        level: response[10],
+        snake_length: u16_at(11),
+        lines: u16_at(13),
+    }
+}
+
+impl GameStatus {
+    /// Score and state in a single line
+    fn summary(&self) -> String {
+        let score = match self.game {
+            Some(Game::Snake) => format!(
+                "Score: {}, Length: {}, Speed: {}",
+                self.score, self.snake_length, self.level
+            ),
+            Some(Game::Pong) => format!("Score: {} - {}", self.score, self.second_score),
+            Some(Game::Tetris) => format!(
+                "Score: {}, Level: {}, Lines: {}",
+                self.score, self.level, self.lines
+            ),
+            Some(Game::GameOfLife) | None => String::new(),
+        };
+        let state = if self.won {
+            "You won! "
+        } else if self.game_over {
+            "Game over! "
+        } else if self.paused {
+            "Paused "
+        } else {
+            ""
+        };
+        format!("{state}{score}")
+    }
+}
+
+/// Play a game on the module, with the keyboard of the computer
+/// Starts the game first, if given. Blocks until quitting or the game is exited.
+fn play_cmd(
+    serialdev: &str,
+    game: Option<Game>,
+    game_args: &GameArgs,
+    greyscale: &GreyscaleOptions,
+) {
+    let start_args = match game {
+        Some(game) => match start_game_args(game, game_args, greyscale) {
+            Some(args) => Some(args),
+            None => return,
+        },
+        None => None,
+    };
+    let mut port = open_serialport(serialdev);
+    if let Some(args) = start_args {
+        simple_cmd_port(&mut port, Command::StartGame, &args);
+    }
+
+    let mut status = get_game_status(&mut port);
+    if status.game.is_none() {
+        println!("No game running. Start one with: play <GAME>");
+        return;
+    }
+    println!("{}", play::HELP);
+
+    let terminal = RawTerminal::enable();
+    let mut shown = String::new();
+    while status.game.is_some() {
+        let summary = status.summary();
+        if summary != shown {
+            terminal.show_line(&summary);
+            shown = summary;
+        }
+
+        // Keep checking the status, even when no key is pressed
+        let control = match terminal.read_key(Duration::from_millis(100)) {
+            Some(Key::Quit) => {
+                simple_cmd_port(
+                    &mut port,
+                    Command::GameControl,
+                    &[GameControlArg::Exit as u8],
+                );
+                break;
+            }
+            Some(Key::Up) => Some(GameControlArg::Up),
+            Some(Key::Down) => Some(GameControlArg::Down),
+            Some(Key::Left) => Some(GameControlArg::Left),
+            Some(Key::Right) => Some(GameControlArg::Right),
+            Some(Key::SecondLeft) => Some(GameControlArg::SecondLeft),
+            Some(Key::SecondRight) => Some(GameControlArg::SecondRight),
+            // Only used across two modules
+            Some(Key::SecondUp | Key::SecondDown) => None,
+            Some(Key::Pause) if status.paused => Some(GameControlArg::Resume),
+            Some(Key::Pause) => Some(GameControlArg::Pause),
+            Some(Key::Restart) => Some(GameControlArg::Restart),
+            None => None,
+        };
+        if let Some(control) = control {
+            simple_cmd_port(&mut port, Command::GameControl, &[control as u8]);
+        }
+        status = get_game_status(&mut port);
+    }
+}
+
+/// Play a game across two modules, stepped here and streamed to them frame by frame
+fn play_dual_cmd(serialdevs: &[String], args: &PlayDualArgs, vsync: bool) {
+    if serialdevs.len() < 2 {
+        println!(
+            "Need two LED Matrix modules, found {}. Don't select one with --serial-dev",
+            serialdevs.len()
+        );
+        return;
+    }
+    let mut ports: Vec<Box<dyn SerialPort>> = serialdevs[..2]
+        .iter()
+        .map(|dev| open_serialport(dev))
+        .collect();
+    if args.swap_sides {
+        ports.swap(0, 1);
+    }
+    // A game running on the module itself would draw over the frames
+    for port in &mut ports {
+        simple_cmd_port(port, Command::GameControl, &[GameControlArg::Exit as u8]);
+    }
+
+    let mut game = DualState::new(args);
+    let mut paused = false;
+    println!("{}", dual::HELP);
+
+    let terminal = RawTerminal::enable();
+    let mut shown = None;
+    let mut shown_summary = String::new();
+    let mut next_step = Instant::now() + game.step_period();
+    loop {
+        // Only send frames that changed, a key press doesn't always change anything
+        let frames = game.frames();
+        if shown.as_ref() != Some(&frames) {
+            for (port, frame) in ports.iter_mut().zip([&frames.0, &frames.1]) {
+                for (x, col) in frame.iter().enumerate() {
+                    send_col(port, x as u8, col);
+                }
+                show_cols(port, vsync);
+            }
+            shown = Some(frames);
+        }
+        let summary = game.summary(paused);
+        if summary != shown_summary {
+            terminal.show_line(&summary);
+            shown_summary = summary;
+        }
+
+        match terminal.read_key(next_step.saturating_duration_since(Instant::now())) {
+            Some(Key::Quit) => break,
+            Some(Key::Pause) => paused = !paused,
+            Some(Key::Restart) => {
+                game = game.restarted();
+                paused = false;
+            }
+            Some(key) if !paused => game.handle_key(key),
+            _ => {}
+        }
+        if Instant::now() >= next_step {
+            if !paused {
+                game.step();
+            }
+            next_step = Instant::now() + game.step_period();
+        }
+    }
+}
+
+fn game_status_cmd(serialdev: &str) {
+    let mut port = open_serialport(serialdev);
+    let status = get_game_status(&mut port);
+
+    let Some(game) = status.game else {
+        println!("No game running");
+        return;
+    };
+    println!("Game: {game:?}");
+    let state = if status.won {
+        "Won"
+    } else if status.game_over {
+        "Game over"
+    } else if status.paused {
+        "Paused"
+    } else {
+        "Running"
+    };
+    println!("Status: {state}");
+    match game {
+        Game::Snake => {
+            println!("Score: {}", status.score);
+            println!("Length: {}", status.snake_length);
+            println!("Speed: {}", status.level);
+        }
+        Game::Pong => {
+            println!("Score: {} - {}", status.score, status.second_score);
+            println!("Speed: {}", status.level);
+        }
+        Game::Tetris => {
+            println!("Score: {}", status.score);
+            println!("Level: {}", status.level);
+            println!("Lines: {}", status.lines);
+        }
+        Game::GameOfLife => {}
+    }
+}
+
+fn game_speed_cmd(serialdev: &str, arg: Option<u16>) {
+    let mut port = open_serialport(serialdev);
+
+    if let Some(period) = arg {
+        simple_cmd_port(&mut port, Command::GameSpeed, &period.to_le_bytes());
+    } else {
+        simple_cmd_port(&mut port, Command::GameSpeed, &[]);
+
+        let mut response: Vec<u8> = vec![0; 32];
+        port.read_exact(response.as_mut_slice())
+            .expect("Found no data!");
+
+        let period = u16::from_le_bytes([response[0], response[1]]);
+        if period == 0 {
+            println!("No game running");
+        } else if response[2] == 1 {
+            println!("Game step period: {}ms", period);
+        } else {
+            println!("Game step period: {}ms (picked by the game)", period);
         }
-        (Game::GameOfLife, None) => {
-            println!("To start Game of Life, provide a --game-param");
+    }
+}
+
+/// Games that keep high scores
+const HIGH_SCORE_GAMES: [Game; 3] = [Game::Snake, Game::Pong, Game::Tetris];
+
+fn high_scores_cmd(serialdev: &str) {
+    let mut port = open_serialport(serialdev);
+
+    for game in HIGH_SCORE_GAMES {
+        simple_cmd_port(&mut port, Command::HighScores, &[game as u8]);
+
+        port.set_timeout(FLASH_TIMEOUT).unwrap();
+        let mut response: Vec<u8> = vec![0; 32];
+        port.read_exact(response.as_mut_slice())
+            .expect("Found no data!");
+        port.set_timeout(SERIAL_TIMEOUT).unwrap();
+
+        let scores: Vec<String> = response[0..20]
+            .chunks(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .filter(|&score| score > 0)
+            .map(|score| score.to_string())
+            .collect();
+        if scores.is_empty() {
+            println!("{game:?}: No high scores yet");
+        } else {
+            println!("{game:?}: {}", scores.join(", "));
         }
-        (_, _) => simple_cmd(serialdev, Command::StartGame, &[game as u8]),
+    }
+}
+
+fn reset_high_scores_cmd(serialdev: &str) {
+    let mut port = open_serialport(serialdev);
+
+    for game in HIGH_SCORE_GAMES {
+        simple_cmd_port(&mut port, Command::HighScores, &[game as u8, 0x01]);
     }
 }
 
@@ -496,6 +1084,44 @@ fn brightness_cmd(serialdev: &str, arg: Option<u8>) {
     }
 }
 
+/// Read back the brightness of each LED and save it as a greyscale image
+/// Also prints what's currently running on the module.
+fn ledmatrix_screenshot_cmd(serialdev: &str, path: &str) {
+    const LEDS: usize = WIDTH * HEIGHT;
+    let mut port = open_serialport(serialdev);
+
+    let mut leds = Vec::with_capacity(LEDS);
+    let mut response: Vec<u8> = vec![0; 32];
+    while leds.len() < LEDS {
+        let offset = leds.len() as u16;
+        simple_cmd_port(&mut port, Command::GetGrid, &offset.to_le_bytes());
+        port.read_exact(response.as_mut_slice())
+            .expect("Found no data!");
+        let len = (response.len() - 2).min(LEDS - leds.len());
+        leds.extend_from_slice(&response[2..2 + len]);
+    }
+
+    let flags = response[0];
+    println!("Brightness: {}", response[1]);
+    println!("Animating: {}", flags & 0x01 != 0);
+    println!("Game running: {}", flags & 0x02 != 0);
+    println!("Animation playing: {}", flags & 0x04 != 0);
+    println!("Clock running: {}", flags & 0x08 != 0);
+
+    let img = GrayImage::from_fn(
+        WIDTH as u32 * SCREENSHOT_SCALE,
+        HEIGHT as u32 * SCREENSHOT_SCALE,
+        |x, y| {
+            let (x, y) = (
+                (x / SCREENSHOT_SCALE) as usize,
+                (y / SCREENSHOT_SCALE) as usize,
+            );
+            Luma([leds[x + y * WIDTH]])
+        },
+    );
+    img.save(path).expect("Failed to save screenshot");
+}
+
 fn animate_cmd(serialdev: &str, arg: Option<bool>) {
     let mut port = serialport::new(serialdev, 115_200)
         .timeout(SERIAL_TIMEOUT)
@@ -530,6 +1156,26 @@ fn commit_cols(port: &mut Box<dyn SerialPort>) {
     simple_cmd_port(port, Command::CommitCols, &[]);
 }
 
+/// Show the columns from send_col() on the next frame of the firmware and wait until it's shown.
+/// Frames then never change while the firmware is in the middle of rendering or animating one.
+fn present_cols(port: &mut Box<dyn SerialPort>) {
+    simple_cmd_port(port, Command::Present, &[0x01]);
+    port.set_timeout(PRESENT_TIMEOUT).unwrap();
+    let mut response: Vec<u8> = vec![0; 32];
+    port.read_exact(response.as_mut_slice())
+        .expect("Found no data!");
+    port.set_timeout(SERIAL_TIMEOUT).unwrap();
+}
+
+/// Show the columns from send_col(), either right away or in step with the firmware
+fn show_cols(port: &mut Box<dyn SerialPort>, vsync: bool) {
+    if vsync {
+        present_cols(port);
+    } else {
+        commit_cols(port);
+    }
+}
+
 ///Increase the brightness with each pixel.
 ///Only 0-255 available, so it can't fill all 306 LEDs
 fn all_brightnesses_cmd(serialdev: &str) {
@@ -589,22 +1235,46 @@ fn breathing_cmd(serialdevs: &Vec<String>) {
     }
 }
 
+/// Load an image and scale it to the size of the matrix
+fn load_matrix_image(image_path: &str, landscape: bool, options: &GreyscaleOptions) -> GrayImage {
+    let img = ImageReader::open(image_path).unwrap().decode().unwrap();
+    convert_matrix_image(&img, landscape, options)
+}
+
+/// Load an image and only scale it to the size of the matrix, for black and white
+/// Gamma and contrast would move which pixels end up on.
+fn load_bw_matrix_image(
+    image_path: &str,
+    landscape: bool,
+    filter: imageops::FilterType,
+) -> GrayImage {
+    let img = ImageReader::open(image_path).unwrap().decode().unwrap();
+    scale_matrix_image(&img, landscape, filter)
+}
+
+/// Whether the image is an animated GIF or PNG with more than one frame
+fn is_animated(image_path: &str) -> bool {
+    decode_animation(image_path)
+        .map(|frames| frames.take(2).count() > 1)
+        .unwrap_or(false)
+}
+
 /// Display an image in black and white
 /// Confirmed working with PNG and GIF.
-/// Must be 9x34 in size.
 /// Sends everything in a single command
-fn display_bw_image_cmd(serialdev: &str, image_path: &str) {
-    let mut vals: [u8; 39] = [0; 39];
+fn display_bw_image_cmd(
+    serialdev: &str,
+    image_path: &str,
+    landscape: bool,
+    options: &GreyscaleOptions,
+) {
+    let img = load_bw_matrix_image(image_path, landscape, options.filter);
+    simple_cmd(serialdev, Command::DisplayBwImage, &bw_image_bytes(&img));
+}
 
-    let img = ImageReader::open(image_path)
-        .unwrap()
-        .decode()
-        .unwrap()
-        .to_luma8();
-    let width = img.width();
-    let height = img.height();
-    assert!(width == 9);
-    assert!(height == 34);
+/// Pack the image into one bit per LED, bright pixels are on
+fn bw_image_bytes(img: &GrayImage) -> [u8; 39] {
+    let mut vals: [u8; 39] = [0; 39];
     for (x, y, pixel) in img.enumerate_pixels() {
         let brightness = pixel.0[0];
         if brightness > 0xFF / 2 {
@@ -612,56 +1282,62 @@ fn display_bw_image_cmd(serialdev: &str, image_path: &str) {
             vals[i / 8] |= 1 << (i % 8);
         }
     }
-
-    simple_cmd(serialdev, Command::DisplayBwImage, &vals);
-}
-
-// Calculate pixel brightness from an RGB triple
-fn pixel_to_brightness(pixel: &Luma<u8>) -> u8 {
-    let brightness = pixel.0[0];
-    // Poor man's scaling to make the greyscale pop better.
-    // Should find a good function.
-    if brightness > 200 {
-        brightness
-    } else if brightness > 150 {
-        ((brightness as u32) * 10 / 8) as u8
-    } else if brightness > 100 {
-        brightness / 2
-    } else if brightness > 50 {
-        brightness
-    } else {
-        brightness * 2
-    }
+    vals
 }
 
 /// Display an image in greyscale
 /// Sends each 1x34 column and then commits => 10 commands
-fn display_gray_image_cmd(serialdev: &str, image_path: &str) {
+fn display_gray_image_cmd(
+    serialdev: &str,
+    image_path: &str,
+    landscape: bool,
+    options: &GreyscaleOptions,
+) {
     let mut port = serialport::new(serialdev, 115_200)
         .timeout(SERIAL_TIMEOUT)
         .open()
         .expect("Failed to open port");
 
-    let img = ImageReader::open(image_path)
-        .unwrap()
-        .decode()
-        .unwrap()
-        .to_luma8();
-    let width = img.width();
-    let height = img.height();
-    assert!(width == 9);
-    assert!(height == 34);
+    let img = load_matrix_image(image_path, landscape, options);
+    send_gray_img(&mut port, &img, false);
+}
+
+/// Play an animated GIF or PNG in greyscale, with the delays of each frame
+/// Loops forever
+fn animated_gray_image_cmd(
+    serialdevs: &[String],
+    image_path: &str,
+    landscape: bool,
+    options: &GreyscaleOptions,
+    vsync: bool,
+) {
+    let mut ports: Vec<Box<dyn SerialPort>> =
+        serialdevs.iter().map(|dev| open_serialport(dev)).collect();
+    let frames: Vec<VideoFrame> = animation_frames(decode_animation(image_path).unwrap(), |img| {
+        convert_matrix_image(&img, landscape, options)
+    })
+    .collect();
+
+    loop_animation(&frames, |img| {
+        for port in &mut ports {
+            send_gray_img(port, img, vsync);
+        }
+    });
+}
+
+/// Send a 9x34 greyscale image
+/// Sends each 1x34 column and then commits or presents => 10 commands
+fn send_gray_img(port: &mut Box<dyn SerialPort>, img: &GrayImage, vsync: bool) {
     for x in 0..WIDTH {
         let mut vals: [u8; HEIGHT] = [0; HEIGHT];
 
         for y in 0..HEIGHT {
-            let pixel = img.get_pixel(x as u32, y as u32);
-            vals[y] = pixel_to_brightness(pixel);
+            vals[y] = img.get_pixel(x as u32, y as u32).0[0];
         }
 
-        send_col(&mut port, x as u8, &vals)
+        send_col(port, x as u8, &vals)
     }
-    commit_cols(&mut port);
+    show_cols(port, vsync);
 }
 
 /// Display an equlizer looking animation with random values.
@@ -801,23 +1477,39 @@ fn render_matrix(serialdev: &str, matrix: &[[u8; 34]; 9]) {
     simple_cmd(serialdev, Command::DisplayBwImage, &vals);
 }
 
-/// Render the current time and display.
-/// Loops forever, updating every second
-fn clock_cmd(serialdevs: &Vec<String>) {
+/// Show the clock and keep setting the time, so that it doesn't drift
+/// After stopping, the clock keeps running on the module.
+fn clock_cmd(serialdevs: &[String], face: ClockFace) {
+    set_time_cmd(serialdevs);
+    for serialdev in serialdevs {
+        simple_cmd(serialdev, Command::ClockMode, &[face as u8]);
+    }
     loop {
-        let date = Local::now();
-        let current_time = date.format("%H:%M").to_string();
-        println!("Current Time = {current_time}");
+        thread::sleep(CLOCK_SYNC_INTERVAL);
+        set_time_cmd(serialdevs);
+    }
+}
 
-        for serialdev in serialdevs {
-            show_string(serialdev, &current_time);
-        }
-        thread::sleep(Duration::from_millis(1000));
+/// Set the time of the modules to the local time
+fn set_time_cmd(serialdevs: &[String]) {
+    // Wait for the start of the next second. The module can only be set to
+    // full seconds, so it would lag behind by the fraction otherwise.
+    let subsec_millis = Local::now().timestamp_subsec_millis() % 1000;
+    thread::sleep(Duration::from_millis(1000 - subsec_millis as u64));
+
+    let now = Local::now();
+    println!("Setting time to {}", now.format("%H:%M:%S"));
+    for serialdev in serialdevs {
+        let time = [now.hour() as u8, now.minute() as u8, now.second() as u8];
+        simple_cmd(serialdev, Command::SetTime, &time);
     }
 }
 
 /// Render a string with up to five letters
 fn show_string(serialdev: &str, s: &str) {
+    if s.chars().count() > 5 {
+        println!("Only showing the first 5 characters. Use --scroll to display longer text");
+    }
     let items: Vec<Vec<u8>> = s.chars().take(5).map(convert_font).collect();
     show_font(serialdev, &items);
 }
@@ -850,6 +1542,187 @@ fn show_symbols(serialdev: &str, symbols: &Vec<String>) {
     show_font(serialdev, &font_items);
 }
 
+/// Render a string in landscape, reading from left to right
+/// Fits 8 characters in the small font, 5 in the standard one
+fn show_landscape_string(serialdev: &str, s: &str, font: Font) {
+    let frame = landscape_text(s, font);
+    render_matrix(serialdev, &rotate_landscape(&frame));
+}
+
+/// Render a list of symbols in landscape
+/// Symbols always use the standard font, letters and numbers the given one
+fn show_landscape_symbols(serialdev: &str, symbols: &[String], font: Font) {
+    println!("Symbols: {symbols:?}");
+    let text: String = symbols
+        .iter()
+        .map(|symbol| {
+            if symbol.chars().count() == 1 {
+                symbol.clone()
+            } else {
+                format!("{{{symbol}}}")
+            }
+        })
+        .collect();
+    show_landscape_string(serialdev, &text, font);
+}
+
+/// Scroll text across the display, rendering every frame on the host.
+/// Loops forever
+fn scroll_cmd(
+    serialdevs: &[String],
+    text: &str,
+    font: Font,
+    direction: ScrollDirection,
+    speed: u16,
+    smooth: bool,
+    vsync: bool,
+) {
+    let mut ports: Vec<Box<dyn SerialPort>> =
+        serialdevs.iter().map(|dev| open_serialport(dev)).collect();
+    let strip = scroll_strip(text, font, direction);
+    let speed = speed.max(1) as f32;
+
+    // Smooth scrolling moves by fractions of a pixel at a fixed framerate,
+    // otherwise move by a full pixel every frame
+    let (frame_time, step) = if smooth {
+        (Duration::from_secs_f32(1.0 / 30.0), speed / 30.0)
+    } else {
+        (Duration::from_secs_f32(1.0 / speed), 1.0)
+    };
+
+    let mut offset = 0.0;
+    loop {
+        let start = Instant::now();
+        let frame = scroll_frame(&strip, offset, direction);
+        for port in &mut ports {
+            for (x, col) in frame.iter().enumerate() {
+                send_col(port, x as u8, col);
+            }
+            show_cols(port, vsync);
+        }
+
+        offset = (offset + step) % strip.len() as f32;
+        thread::sleep(frame_time.saturating_sub(start.elapsed()));
+    }
+}
+
+/// Send the text to the firmware and let it do the scrolling.
+/// Only black and white, the firmware doesn't blend between rows
+fn scroll_on_device_cmd(
+    serialdev: &str,
+    text: &str,
+    font: Font,
+    direction: ScrollDirection,
+    speed: u16,
+) {
+    let strip = scroll_strip(text, font, direction);
+    if strip.len() > SCROLL_STRIP_ROWS {
+        println!(
+            "Text too long to scroll on the device. Needs {} rows, can fit {}",
+            strip.len(),
+            SCROLL_STRIP_ROWS
+        );
+        return;
+    }
+
+    let mut port = open_serialport(serialdev);
+    for (i, rows) in strip.chunks(SCROLL_ROWS_PER_COMMAND).enumerate() {
+        let offset = ((i * SCROLL_ROWS_PER_COMMAND) as u16).to_le_bytes();
+        let mut args = vec![offset[0], offset[1]];
+        for row in rows {
+            let mut bits: u16 = 0;
+            for (x, brightness) in row.iter().enumerate() {
+                if *brightness > 0xFF / 2 {
+                    bits |= 1 << x;
+                }
+            }
+            args.extend_from_slice(&bits.to_le_bytes());
+        }
+        simple_cmd_port(&mut port, Command::StageScrollRows, &args);
+    }
+
+    // The firmware moves by one row every few animation frames
+    simple_cmd_port(&mut port, Command::AnimationPeriod, &[]);
+    let mut response: Vec<u8> = vec![0; 32];
+    port.read_exact(response.as_mut_slice())
+        .expect("Found no data!");
+    let period_ms = u16::from_le_bytes([response[0], response[1]]).max(1);
+    let frames_per_step = (1000 / speed.max(1) / period_ms).clamp(1, 255) as u8;
+
+    const SCROLL_LOOP: u8 = 0b01;
+    const SCROLL_FROM_TOP: u8 = 0b10;
+    let flags = match direction {
+        ScrollDirection::Vertical => SCROLL_LOOP,
+        ScrollDirection::Horizontal => SCROLL_LOOP | SCROLL_FROM_TOP,
+    };
+    let len = (strip.len() as u16).to_le_bytes();
+    simple_cmd_port(
+        &mut port,
+        Command::StartScroll,
+        &[len[0], len[1], frames_per_step, flags],
+    );
+}
+
+/// Show frames at the time they're due.
+/// If sending a frame took so long that the next one is already due, the
+/// frame is dropped, to stay in sync with the video.
+fn play_video(frames: impl Iterator<Item = VideoFrame>, mut show: impl FnMut(&GrayImage)) {
+    let start = Instant::now();
+    let mut frames = frames.peekable();
+    let mut dropped = 0;
+    while let Some(frame) = frames.next() {
+        if let Some(next) = frames.peek() {
+            if start.elapsed() >= next.timestamp {
+                dropped += 1;
+                continue;
+            }
+        }
+        thread::sleep(frame.timestamp.saturating_sub(start.elapsed()));
+        show(&frame.image);
+    }
+    if dropped > 0 {
+        println!("Dropped {dropped} frames, because the display couldn't keep up");
+    }
+}
+
+/// Play the frames of an animation over and over again
+fn loop_animation(frames: &[VideoFrame], mut show: impl FnMut(&GrayImage)) -> ! {
+    // Show the last frame for as long as the others, before starting over
+    let total_duration = frames
+        .last()
+        .map(|frame| frame.timestamp + frame.duration)
+        .unwrap_or_default();
+
+    loop {
+        let start = Instant::now();
+        play_video(frames.iter().cloned(), &mut show);
+        thread::sleep(total_duration.saturating_sub(start.elapsed()));
+    }
+}
+
+/// Play a video in greyscale
+/// Blocks until the video is over
+fn video_cmd(
+    serialdevs: &[String],
+    video_path: &str,
+    landscape: bool,
+    options: &GreyscaleOptions,
+    vsync: bool,
+) {
+    let mut ports: Vec<Box<dyn SerialPort>> =
+        serialdevs.iter().map(|dev| open_serialport(dev)).collect();
+    let options = *options;
+    let frames = decode_video(video_path, move |img| {
+        convert_matrix_image(&img, landscape, &options)
+    });
+
+    play_video(frames, |img| {
+        for port in &mut ports {
+            send_gray_img(port, img, vsync);
+        }
+    });
+}
+
 fn display_on_cmd(serialdev: &str, arg: Option<bool>) {
     let mut port = serialport::new(serialdev, 115_200)
         .timeout(SERIAL_TIMEOUT)
@@ -910,6 +1783,107 @@ fn screensaver_cmd(serialdev: &str, arg: Option<bool>) {
     }
 }
 
+/// Change what the screensaver shows and when it starts
+/// Without any changes, print the current settings.
+fn screensaver_mode_cmd(
+    serialdev: &str,
+    mode: Option<ScreenSaverMode>,
+    timeout: Option<u16>,
+    on_wake: Option<bool>,
+) {
+    let mut port = serialport::new(serialdev, 115_200)
+        .timeout(SERIAL_TIMEOUT)
+        .open()
+        .expect("Failed to open port");
+
+    simple_cmd_port(&mut port, Command::ScreenSaverMode, &[]);
+    let mut response: Vec<u8> = vec![0; 32];
+    port.read_exact(response.as_mut_slice())
+        .expect("Found no data!");
+    let current_mode = response[0];
+    let current_timeout = u16::from_le_bytes([response[1], response[2]]);
+    let current_on_wake = response[3] == 1;
+
+    if mode.is_none() && timeout.is_none() && on_wake.is_none() {
+        let mode = <ScreenSaverMode as clap::ValueEnum>::value_variants()
+            .iter()
+            .find(|mode| **mode as u8 == current_mode);
+        println!("Screensaver mode: {mode:?}");
+        println!("Timeout: {current_timeout}s");
+        println!("Starts after waking up: {current_on_wake}");
+        return;
+    }
+
+    let mut vals = vec![mode.map_or(current_mode, |mode| mode as u8)];
+    vals.extend_from_slice(&timeout.unwrap_or(current_timeout).to_le_bytes());
+    vals.push(on_wake.unwrap_or(current_on_wake) as u8);
+    simple_cmd_port(&mut port, Command::ScreenSaverMode, &vals);
+}
+
+/// Change when the screensaver starts, because the content didn't change
+/// Without any changes, print the current settings.
+fn idle_protection_cmd(serialdev: &str, timeout: Option<u16>, mode: Option<ScreenSaverMode>) {
+    let mut port = serialport::new(serialdev, 115_200)
+        .timeout(SERIAL_TIMEOUT)
+        .open()
+        .expect("Failed to open port");
+
+    simple_cmd_port(&mut port, Command::IdleProtection, &[]);
+    let mut response: Vec<u8> = vec![0; 32];
+    port.read_exact(response.as_mut_slice())
+        .expect("Found no data!");
+    let current_timeout = u16::from_le_bytes([response[0], response[1]]);
+    let current_mode = response[2];
+
+    if timeout.is_none() && mode.is_none() {
+        let mode = <ScreenSaverMode as clap::ValueEnum>::value_variants()
+            .iter()
+            .find(|mode| **mode as u8 == current_mode);
+        if current_timeout == 0 {
+            println!("Idle protection: Off");
+        } else {
+            println!("Idle protection: After {current_timeout}s");
+        }
+        println!("Screensaver mode: {mode:?}");
+        return;
+    }
+
+    let mut vals = timeout.unwrap_or(current_timeout).to_le_bytes().to_vec();
+    vals.push(mode.map_or(current_mode, |mode| mode as u8));
+    simple_cmd_port(&mut port, Command::IdleProtection, &vals);
+}
+
+/// Upload an image in black and white, for the bitmap screensaver
+/// Images larger than the screen are scaled down to fit.
+fn screensaver_bitmap_cmd(serialdev: &str, image_path: &str, dither: Dither) {
+    let img = ImageReader::open(image_path).unwrap().decode().unwrap();
+    let mut img = if img.width() > B1_WIDTH || img.height() > B1_HEIGHT {
+        img.resize(B1_WIDTH, B1_HEIGHT, imageops::FilterType::Triangle)
+            .into_luma8()
+    } else {
+        img.into_luma8()
+    };
+    dither_image(&mut img, dither);
+
+    // Row by row, 8 pixels per byte
+    let mut bytes = vec![0; ((img.width() * img.height()) as usize).div_ceil(8)];
+    for (i, pixel) in img.pixels().enumerate() {
+        if pixel.0[0] < 0x80 {
+            bytes[i / 8] |= 1 << (i % 8);
+        }
+    }
+
+    let mut serialport = open_serialport(serialdev);
+    for (i, chunk) in bytes.chunks(BITMAP_CHUNK_BYTES).enumerate() {
+        let mut vals = vec![];
+        vals.extend_from_slice(&(img.width() as u16).to_le_bytes());
+        vals.extend_from_slice(&(img.height() as u16).to_le_bytes());
+        vals.extend_from_slice(&((i * BITMAP_CHUNK_BYTES) as u16).to_le_bytes());
+        vals.extend_from_slice(chunk);
+        simple_open_cmd(&mut serialport, Command::UploadBitmap, &vals);
+    }
+}
+
 fn fps_cmd(serialdev: &str, arg: Option<Fps>) {
     const HIGH_FPS_MASK: u8 = 0b00010000;
     const LOW_FPS_MASK: u8 = 0b00000111;
@@ -1073,93 +2047,253 @@ fn set_color_cmd(serialdev: &str, color: Color) {
     simple_cmd(serialdev, Command::SetColor, args);
 }
 
-fn gif_cmd(serialdev: &str, image_path: &str) {
+/// Play an animated GIF or PNG in black and white, with the delays of each frame
+/// Loops forever
+fn gif_cmd(serialdev: &str, image_path: &str, fit: ImageFit, dither: Dither) {
     let mut serialport = open_serialport(serialdev);
-
-    loop {
-        let img = std::fs::File::open(image_path).unwrap();
-        let gif = GifDecoder::new(img).unwrap();
-        let frames = gif.into_frames();
-        for frame in frames {
-            let frame = frame.unwrap();
-            //let delay = frame.delay();
-            //println!("  Delay: {:?}", Duration::from(delay));
-            let frame_img = frame.into_buffer();
-            let frame_img = DynamicImage::from(frame_img);
-            let frame_img = frame_img.resize(300, 400, image::imageops::FilterType::Gaussian);
-            let frame_img = frame_img.into_luma8();
-            display_img(&mut serialport, &frame_img);
-            // Not delaying any further. Current transmission delay is big enough
-            //thread::sleep(delay.into());
-        }
-    }
+    let animation = decode_animation(image_path).expect("Not an animated GIF or PNG");
+    let frames: Vec<VideoFrame> = animation_frames(animation, |img| {
+        let mut img = fit_image(&img, B1_WIDTH, B1_HEIGHT, fit);
+        dither_image(&mut img, dither);
+        img
+    })
+    .collect();
+
+    let mut previous: Option<GrayImage> = None;
+    loop_animation(&frames, |img| {
+        update_bw_img(&mut serialport, previous.as_ref(), img);
+        previous = Some(img.clone());
+    });
 }
 
 /// Display an image in black and white
 /// Confirmed working with PNG and GIF.
-/// Must be 300x400 in size.
-/// Sends one 400px column in a single commands and a flush at the end
-fn generic_img_cmd(serialdev: &str, image_path: &str) {
+/// Images of any size are scaled to fit the screen.
+fn generic_img_cmd(serialdev: &str, image_path: &str, fit: ImageFit, dither: Dither) {
     let mut serialport = open_serialport(serialdev);
-    let img = ImageReader::open(image_path)
-        .unwrap()
-        .decode()
-        .unwrap()
-        .to_luma8();
-    display_img(&mut serialport, &img);
-}
-
-fn b1display_bw_image_cmd(serialdev: &str, image_path: &str) {
-    generic_img_cmd(serialdev, image_path);
-}
-
-fn display_img(serialport: &mut Box<dyn SerialPort>, img: &ImageBuffer<Luma<u8>, Vec<u8>>) {
-    let width = img.width();
-    let height = img.height();
-    assert!(width == 300);
-    assert!(height == 400);
-
-    let (brightest, darkest) = img
-        .pixels()
-        .fold((0xFF, 0x00), |(brightest, darkest), pixel| {
-            let br = pixel.0[0];
-            let brightest = if br > brightest { br } else { brightest };
-            let darkest = if br < darkest { br } else { darkest };
-            (brightest, darkest)
-        });
-    let bright_diff = brightest - darkest;
-    // Anything brighter than 90% between darkest and brightest counts as white
-    // Just a heuristic. Don't use greyscale images! Use black and white instead
-    let threshold = darkest + (bright_diff / 10) * 9;
+    let img = ImageReader::open(image_path).unwrap().decode().unwrap();
+    display_img(&mut serialport, &img, fit, dither);
+}
 
-    for x in 0..300 {
-        let mut vals: [u8; 2 + 50] = [0; 2 + 50];
-        let column = (x as u16).to_le_bytes();
-        vals[0] = column[0];
-        vals[1] = column[1];
+fn b1display_bw_image_cmd(serialdev: &str, image_path: &str, fit: ImageFit, dither: Dither) {
+    generic_img_cmd(serialdev, image_path, fit, dither);
+}
+
+/// Play a video, dithered to black and white
+/// Blocks until the video is over
+fn b1display_video_cmd(serialdev: &str, video_path: &str, fit: ImageFit, dither: Dither) {
+    let mut serialport = open_serialport(serialdev);
+    let frames = decode_video(video_path, move |img| {
+        let mut img = fit_image(&img, B1_WIDTH, B1_HEIGHT, fit);
+        dither_image(&mut img, dither);
+        img
+    });
+    let mut previous: Option<GrayImage> = None;
+    play_video(frames, |img| {
+        update_bw_img(&mut serialport, previous.as_ref(), img);
+        previous = Some(img.clone());
+    });
+}
+
+/// Read back the firmware's copy of the framebuffer and save it as an image
+/// The screensaver and inverting the screen aren't part of it.
+fn b1display_screenshot_cmd(serialdev: &str, path: &str) {
+    const FRAMEBUFFER_BYTES: usize = (B1_WIDTH * B1_HEIGHT / 8) as usize;
+    let mut port = open_serialport(serialdev);
+
+    let mut framebuffer = Vec::with_capacity(FRAMEBUFFER_BYTES);
+    while framebuffer.len() < FRAMEBUFFER_BYTES {
+        let offset = framebuffer.len() as u16;
+        simple_cmd_port(&mut port, Command::GetFramebuffer, &offset.to_le_bytes());
+        let mut response: Vec<u8> = vec![0; 32];
+        port.read_exact(response.as_mut_slice())
+            .expect("Found no data!");
+        let len = response.len().min(FRAMEBUFFER_BYTES - framebuffer.len());
+        framebuffer.extend_from_slice(&response[..len]);
+    }
+
+    unpack_framebuffer(&framebuffer)
+        .save(path)
+        .expect("Failed to save screenshot");
+}
 
-        let mut byte: u8 = 0;
-        for y in 0..400usize {
-            let pixel = img.get_pixel(x, y as u32);
-            let brightness = pixel.0[0];
-            let black = brightness < threshold;
+/// Scale and dither an image to black and white and display it
+fn display_img(
+    serialport: &mut Box<dyn SerialPort>,
+    img: &DynamicImage,
+    fit: ImageFit,
+    dither: Dither,
+) {
+    let mut img = fit_image(img, B1_WIDTH, B1_HEIGHT, fit);
+    dither_image(&mut img, dither);
+    update_bw_img(serialport, None, &img);
+}
 
-            let bit = y % 8;
-            if bit == 0 {
-                byte = 0;
+/// Send only the parts of a black and white image that changed since the previous one
+/// Without a previous image, the whole image is sent.
+fn update_bw_img(
+    serialport: &mut Box<dyn SerialPort>,
+    previous: Option<&ImageBuffer<Luma<u8>, Vec<u8>>>,
+    img: &ImageBuffer<Luma<u8>, Vec<u8>>,
+) {
+    let previous_framebuffer = previous.map(pack_framebuffer);
+    let rle = encode_rle(previous_framebuffer.as_deref(), &pack_framebuffer(img));
+    if rle.is_empty() {
+        return;
+    }
+
+    // Run-length encoding is usually smaller, but rectangles can be better
+    // for small changes spread out over many columns
+    let rects = previous.map(|previous| dirty_rects(previous, img));
+    match rects {
+        Some(rects) if rects.iter().map(DirtyRect::commands).sum::<u32>() < rle.len() as u32 => {
+            for rect in &rects {
+                send_bw_rect(serialport, img, rect);
             }
-            if black {
-                byte |= 1 << bit;
+        }
+        _ => {
+            for args in &rle {
+                simple_open_cmd(serialport, Command::RleFramebuffer, args);
             }
-            if bit == 7 {
-                vals[2 + y / 8] = byte;
+        }
+    }
+    simple_open_cmd(serialport, Command::FlushFramebuffer, &[]);
+}
+
+/// Send a rectangle of a black and white image, without flushing
+/// Split into as many commands as necessary, each with a few rows
+fn send_bw_rect(
+    serialport: &mut Box<dyn SerialPort>,
+    img: &ImageBuffer<Luma<u8>, Vec<u8>>,
+    rect: &DirtyRect,
+) {
+    let rows_per_command = rect.rows_per_command();
+    for strip_y in (rect.y..rect.y + rect.height).step_by(rows_per_command as usize) {
+        let strip_height = rows_per_command.min(rect.y + rect.height - strip_y);
+
+        let mut vals = vec![];
+        for val in [rect.x, strip_y, rect.width, strip_height] {
+            vals.extend_from_slice(&(val as u16).to_le_bytes());
+        }
+        // Flags: Don't flush
+        vals.push(0x00);
+
+        let mut pixels = [0; BLIT_BYTES];
+        for y in 0..strip_height {
+            for x in 0..rect.width {
+                let black = img.get_pixel(rect.x + x, strip_y + y).0[0] < 0x80;
+                let i = (x + y * rect.width) as usize;
+                if black {
+                    pixels[i / 8] |= 1 << (i % 8);
+                }
             }
         }
+        let bytes = ((rect.width * strip_height) as usize).div_ceil(8);
+        vals.extend_from_slice(&pixels[..bytes]);
 
-        simple_open_cmd(serialport, Command::SetPixelColumn, &vals);
+        simple_open_cmd(serialport, Command::BlitRect, &vals);
     }
+}
 
-    simple_open_cmd(serialport, Command::FlushFramebuffer, &[]);
+/// Where and how the firmware should draw text
+struct TextLayout {
+    x: u16,
+    y: u16,
+    font: TextFont,
+    align: TextAlign,
+    /// Wrap lines wider than this many pixels. 0 to not wrap
+    wrap_width: u16,
+    inverted: bool,
+}
+
+/// Let the firmware draw text and flush it to the screen
+fn draw_text_cmd(serialdev: &str, text: &str, layout: &TextLayout) {
+    let mut text_len = text.len().min(TEXT_BYTES);
+    while !text.is_char_boundary(text_len) {
+        text_len -= 1;
+    }
+    if text_len < text.len() {
+        println!("Text too long, only drawing the first {text_len} bytes");
+    }
+
+    let mut vals = vec![];
+    vals.extend_from_slice(&layout.x.to_le_bytes());
+    vals.extend_from_slice(&layout.y.to_le_bytes());
+    vals.push(layout.font as u8);
+    vals.push(layout.align as u8);
+    vals.extend_from_slice(&layout.wrap_width.to_le_bytes());
+    // Flags: Inverted and flush
+    vals.push(layout.inverted as u8 | 0x02);
+    vals.extend_from_slice(&text.as_bytes()[..text_len]);
+
+    simple_cmd(serialdev, Command::DrawText, &vals);
+}
+
+/// Fill a rectangle with black or white and flush it to the screen
+fn clear_region_cmd(serialdev: &str, region: &[u16], black: bool) {
+    let mut vals = vec![];
+    for val in region {
+        vals.extend_from_slice(&val.to_le_bytes());
+    }
+    // Flags: Black and flush
+    vals.push(black as u8 | 0x02);
+
+    simple_cmd(serialdev, Command::ClearRegion, &vals);
+}
+
+/// Show a dashboard on the displays and keep it updated every second
+fn dashboard_cmd(serialdevs: &[String]) {
+    let mut serialports: Vec<Box<dyn SerialPort>> = serialdevs
+        .iter()
+        .map(|serialdev| open_serialport(serialdev))
+        .collect();
+
+    let mut clear_screen = vec![];
+    for val in [0, 0, B1_WIDTH as u16, B1_HEIGHT as u16] {
+        clear_screen.extend_from_slice(&val.to_le_bytes());
+    }
+    // Flags: White and don't flush
+    clear_screen.push(0x00);
+
+    for serialport in &mut serialports {
+        // Start from an empty screen, without any previous widgets
+        simple_open_cmd(serialport, Command::SetWidget, &[0xFF, 0x00]);
+        simple_open_cmd(serialport, Command::ClearRegion, &clear_screen);
+        for widget in dashboard::layout() {
+            set_widget(serialport, &widget);
+        }
+    }
+
+    let mut dashboard = Dashboard::default();
+    loop {
+        let changed = dashboard.changed_widgets();
+        if !changed.is_empty() {
+            for serialport in &mut serialports {
+                for (id, data) in &changed {
+                    // Flags: Don't flush
+                    let mut vals = vec![*id, 0x00];
+                    vals.extend_from_slice(data);
+                    simple_open_cmd(serialport, Command::UpdateWidget, &vals);
+                }
+                simple_open_cmd(serialport, Command::FlushFramebuffer, &[]);
+            }
+        }
+        thread::sleep(Duration::from_millis(1000));
+    }
+}
+
+/// Add a widget to the dashboard, without flushing
+fn set_widget(serialport: &mut Box<dyn SerialPort>, widget: &Widget) {
+    let mut vals = vec![widget.id, widget.kind as u8];
+    for val in [widget.x, widget.y, widget.width, widget.height] {
+        vals.extend_from_slice(&val.to_le_bytes());
+    }
+    vals.push(widget.font as u8);
+    vals.push(widget.align as u8);
+    // Flags: Don't flush
+    vals.push(0x00);
+
+    simple_open_cmd(serialport, Command::SetWidget, &vals);
 }
 
 fn b1_display_color(serialdev: &str, black: bool) {
@@ -1,4 +1,7 @@
-use clap::Parser;
+use std::str::FromStr;
+
+use clap::{Args, Parser, Subcommand};
+use image::imageops::FilterType;
 
 #[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
 #[repr(u8)]
@@ -33,6 +36,90 @@ pub enum GameOfLifeStartParam {
     Beacon = 0x04,
     Glider = 0x05,
     BeaconToadBlinker = 0x06,
+    /// Sent with --seed-image
+    #[value(skip)]
+    Bitmap = 0x07,
+}
+
+/// Life-like rules, which neighbor counts let a cell be born or survive
+/// Bit n is set, if n neighbors are enough.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LifeRules {
+    pub birth: u16,
+    pub survive: u16,
+}
+
+impl FromStr for LifeRules {
+    type Err = String;
+
+    /// Rulestring like B36/S23, or the name of a well known one
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rulestring = match s.to_lowercase().as_str() {
+            "conway" => "B3/S23".to_string(),
+            "highlife" => "B36/S23".to_string(),
+            "seeds" => "B2/S".to_string(),
+            "day-and-night" => "B3678/S34678".to_string(),
+            _ => s.to_uppercase(),
+        };
+        let (birth, survive) = rulestring
+            .split_once('/')
+            .and_then(|(b, s)| Some((b.strip_prefix('B')?, s.strip_prefix('S')?)))
+            .ok_or("Expected a rulestring like B36/S23, or the name of a rule")?;
+        Ok(LifeRules {
+            birth: neighbor_mask(birth)?,
+            survive: neighbor_mask(survive)?,
+        })
+    }
+}
+
+/// Bit mask of neighbor counts, from digits like 23
+fn neighbor_mask(digits: &str) -> Result<u16, String> {
+    digits.chars().try_fold(0, |mask, c| match c.to_digit(10) {
+        Some(n) if n <= 8 => Ok(mask | 1 << n),
+        _ => Err(format!("Not a neighbor count from 0 to 8: {c}")),
+    })
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum Font {
+    /// 5x6 pixels, same as --string
+    Standard,
+    /// 3x5 pixels
+    Small,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum ScrollDirection {
+    /// Characters stacked from top to bottom, scrolling upwards
+    Vertical,
+    /// Characters rotated by 90 degrees, scrolling along the long side
+    Horizontal,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum ResizeFilter {
+    /// Sharp edges, best for pixel art
+    Nearest,
+    /// Linear
+    Triangle,
+    /// Cubic
+    CatmullRom,
+    /// Smooth, slightly blurry
+    Gaussian,
+    /// Sharpest for photos, can cause ringing around edges
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Triangle => FilterType::Triangle,
+            ResizeFilter::CatmullRom => FilterType::CatmullRom,
+            ResizeFilter::Gaussian => FilterType::Gaussian,
+            ResizeFilter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
 }
 
 /// LED Matrix
@@ -76,14 +163,27 @@ pub struct LedMatrixSubcommand {
     #[arg(long)]
     pub breathing: bool,
 
-    /// Display black&white image (9x34px)
+    /// Display black&white image, scaled to fit (9x34px, or 34x9px with --landscape)
     #[arg(long)]
     pub image_bw: Option<String>,
 
-    /// Display grayscale image
+    /// Display grayscale image, scaled to fit. Animated GIFs and PNGs play in a loop
     #[arg(long)]
     pub image_gray: Option<String>,
 
+    /// Filter for scaling images and videos to fit [default: triangle]
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub resize_filter: Option<ResizeFilter>,
+
+    /// Gamma correction of greyscale images and videos. 1.0 is linear
+    #[arg(long, default_value_t = 2.2)]
+    pub gamma: f32,
+
+    /// Stretch images and videos to use the full range of brightness
+    #[arg(long)]
+    pub auto_contrast: bool,
+
     /// Random EQ
     #[arg(long)]
     pub random_eq: bool,
@@ -97,10 +197,14 @@ pub struct LedMatrixSubcommand {
     #[arg(long, num_args(9))]
     pub eq: Option<Vec<u8>>,
 
-    /// Clock
+    /// Show a clock, kept by the module. Keeps running to sync it with the computer's time
     #[arg(long)]
     pub clock: bool,
 
+    /// Set the module's time to the computer's time
+    #[arg(long)]
+    pub set_time: bool,
+
     /// Display a string (max 5 chars)
     #[arg(long)]
     pub string: Option<String>,
@@ -109,21 +213,85 @@ pub struct LedMatrixSubcommand {
     #[arg(long, num_args(0..6))]
     pub symbols: Option<Vec<String>>,
 
-    /// Start a game
+    /// Play a video, scaled to fill the module. Formats other than GIF and APNG require ffmpeg
+    #[arg(long)]
+    pub video: Option<String>,
+
+    /// Show frames of animations, videos and scrolling in step with the firmware, to avoid tearing
+    #[arg(long)]
+    pub vsync: bool,
+
+    /// Lay out images, videos, text and the clock in landscape (34x9) instead of portrait (9x34)
+    #[arg(long)]
+    pub landscape: bool,
+
+    /// Scroll a string of any length. Symbols can be embedded like {sun}
+    #[arg(long)]
+    pub scroll: Option<String>,
+
+    /// Font to render scrolling or landscape text with
     #[arg(long)]
     #[clap(value_enum)]
-    pub start_game: Option<Game>,
+    pub font: Option<Font>,
 
-    /// Paramater for starting the game. Required for some games
+    /// Direction to scroll the text in
     #[arg(long)]
     #[clap(value_enum)]
-    pub game_param: Option<GameOfLifeStartParam>,
+    pub scroll_direction: Option<ScrollDirection>,
+
+    /// Scroll speed in pixels per second
+    #[arg(long, default_value_t = 10)]
+    pub scroll_speed: u16,
+
+    /// Scroll smoothly, using greyscale to show partial pixels
+    #[arg(long)]
+    pub scroll_smooth: bool,
+
+    /// Let the firmware scroll the text, it keeps going after the program exits
+    #[arg(long)]
+    pub scroll_on_device: bool,
+
+    /// Start a game
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub start_game: Option<Game>,
+
+    #[command(flatten)]
+    pub game_args: GameArgs,
 
     /// Stop the currently running game
     #[arg(long)]
     #[clap(value_enum)]
     pub stop_game: bool,
 
+    /// Pause the currently running game
+    #[arg(long)]
+    pub pause_game: bool,
+
+    /// Resume the paused game
+    #[arg(long)]
+    pub resume_game: bool,
+
+    /// Start the currently running game over
+    #[arg(long)]
+    pub restart_game: bool,
+
+    /// Print which game is running, whether it's over and its score
+    #[arg(long)]
+    pub game_status: bool,
+
+    /// Set/get the time between steps of the running game in ms. 0 lets the game pick it
+    #[arg(long)]
+    pub game_speed: Option<Option<u16>>,
+
+    /// Print the best scores of Snake, Pong and Tetris
+    #[arg(long)]
+    pub high_scores: bool,
+
+    /// Clear the best scores of all games
+    #[arg(long)]
+    pub reset_high_scores: bool,
+
     /// Set/get animation FPS
     #[arg(long)]
     pub animation_fps: Option<Option<u16>>,
@@ -144,4 +312,112 @@ pub struct LedMatrixSubcommand {
     /// Get the device version
     #[arg(short, long)]
     pub version: bool,
+
+    /// Save what the LEDs show as an image, scaled up. Prints whether a game or animation runs
+    #[arg(long)]
+    pub screenshot: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<LedMatrixCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LedMatrixCommand {
+    /// Play a game on the module, controlled with the keyboard
+    Play(PlayArgs),
+    /// Play a game across two modules, as one 18x34 playfield run by the computer
+    PlayDual(PlayDualArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct PlayArgs {
+    /// Game to start. Without it, control the game that's already running
+    #[clap(value_enum)]
+    pub game: Option<Game>,
+
+    #[command(flatten)]
+    pub game_args: GameArgs,
+}
+
+/// Games that can span two modules
+#[derive(Copy, Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum DualGame {
+    Pong,
+    Snake,
+}
+
+#[derive(Args, Debug)]
+pub struct PlayDualArgs {
+    /// Game to play
+    #[clap(value_enum)]
+    pub game: DualGame,
+
+    /// Points that win a Pong match. Without it, Pong goes on forever
+    #[arg(long)]
+    pub points_to_win: Option<u8>,
+
+    /// Play Pong against another person, instead of against the computer
+    #[arg(long)]
+    pub two_players: bool,
+
+    /// Let the snake wrap around the edges, instead of hitting them
+    #[arg(long)]
+    pub wrap: bool,
+
+    /// The first module found is on the right, instead of the left
+    #[arg(long)]
+    pub swap_sides: bool,
+}
+
+/// Options for starting a game
+#[derive(Args, Debug)]
+pub struct GameArgs {
+    /// Paramater for starting the game. Required for some games
+    #[arg(long)]
+    #[clap(value_enum)]
+    pub game_param: Option<GameOfLifeStartParam>,
+
+    /// Points that win a Pong match. Without it, Pong goes on forever
+    #[arg(long)]
+    pub points_to_win: Option<u8>,
+
+    /// Play Pong against another person, instead of against the module
+    #[arg(long)]
+    pub two_players: bool,
+
+    /// Let the snake wrap around the edges, instead of hitting them
+    #[arg(long)]
+    pub wrap: bool,
+
+    /// Speed level of the snake at the start, from 0 to 16
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=16))]
+    pub snake_speed: Option<u8>,
+
+    /// Speed levels the snake gains with every food
+    #[arg(long)]
+    pub speed_up: Option<u8>,
+
+    /// Number of obstacles in the way of the snake, up to 32
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=32))]
+    pub obstacles: Option<u8>,
+
+    /// Image to start Game of Life from, instead of --game-param. Bright pixels are alive
+    #[arg(long)]
+    pub seed_image: Option<String>,
+
+    /// Game of Life rules, like B36/S23 or conway, highlife, seeds, day-and-night
+    #[arg(long)]
+    pub rules: Option<LifeRules>,
+
+    /// Cells beyond the edges of Game of Life are dead, instead of wrapping around
+    #[arg(long)]
+    pub dead_borders: bool,
+
+    /// Start Game of Life over from random cells, when nothing changes anymore
+    #[arg(long)]
+    pub auto_restart: bool,
+
+    /// Show older Game of Life cells dimmer than newborn ones
+    #[arg(long)]
+    pub show_age: bool,
 }
@@ -2,9 +2,18 @@
 #![allow(clippy::single_match)]
 mod b1display;
 mod c1minimal;
+mod dashboard;
+mod dirty;
+mod dual;
 mod font;
+mod greyscale;
 mod inputmodule;
 mod ledmatrix;
+mod monochrome;
+mod play;
+mod rle;
+mod text;
+mod video;
 
 use clap::{Parser, Subcommand};
 use inputmodule::find_serialdevs;
@@ -0,0 +1,133 @@
+//! Convert images to black and white for the B1 display
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, Luma};
+
+use crate::b1display::{Dither, ImageFit};
+
+pub const B1_WIDTH: u32 = 300;
+pub const B1_HEIGHT: u32 = 400;
+
+const WHITE: u8 = 0xFF;
+const BLACK: u8 = 0x00;
+
+/// 8x8 Bayer matrix for ordered dithering
+#[rustfmt::skip]
+const BAYER: [[u8; 8]; 8] = [
+    [ 0, 32,  8, 40,  2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44,  4, 36, 14, 46,  6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [ 3, 35, 11, 43,  1, 33,  9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47,  7, 39, 13, 45,  5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Where the error of a pixel goes to, relative to it: (x, y, weight)
+const FLOYD_STEINBERG: &[(i32, i32, i32)] = &[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)];
+const FLOYD_STEINBERG_DIVISOR: i32 = 16;
+/// Only passes on 6/8 of the error, which keeps more contrast
+const ATKINSON: &[(i32, i32, i32)] = &[
+    (1, 0, 1),
+    (2, 0, 1),
+    (-1, 1, 1),
+    (0, 1, 1),
+    (1, 1, 1),
+    (0, 2, 1),
+];
+const ATKINSON_DIVISOR: i32 = 8;
+
+/// Scale the image to the given size, in greyscale
+/// Empty space around the image is white.
+pub fn fit_image(img: &DynamicImage, width: u32, height: u32, fit: ImageFit) -> GrayImage {
+    match fit {
+        ImageFit::Fill => img
+            .resize_to_fill(width, height, FilterType::Triangle)
+            .into_luma8(),
+        ImageFit::Fit => {
+            let scaled = img.resize(width, height, FilterType::Triangle).into_luma8();
+            center_on_white(&scaled, width, height)
+        }
+        ImageFit::Crop => center_on_white(&img.to_luma8(), width, height),
+    }
+}
+
+/// Put the image in the middle of a white canvas, cutting off what doesn't fit
+fn center_on_white(img: &GrayImage, width: u32, height: u32) -> GrayImage {
+    let mut canvas = GrayImage::from_pixel(width, height, Luma([WHITE]));
+    let x = (width as i64 - img.width() as i64) / 2;
+    let y = (height as i64 - img.height() as i64) / 2;
+    imageops::overlay(&mut canvas, img, x, y);
+    canvas
+}
+
+/// Turn a greyscale image into black and white
+/// Afterwards every pixel is either 0x00 or 0xFF
+pub fn dither_image(img: &mut GrayImage, dither: Dither) {
+    match dither {
+        Dither::Threshold => threshold(img),
+        Dither::FloydSteinberg => diffuse_error(img, FLOYD_STEINBERG, FLOYD_STEINBERG_DIVISOR),
+        Dither::Atkinson => diffuse_error(img, ATKINSON, ATKINSON_DIVISOR),
+        Dither::Bayer => ordered(img),
+    }
+}
+
+fn threshold(img: &mut GrayImage) {
+    let (brightest, darkest) = img
+        .pixels()
+        .fold((0x00, 0xFF), |(brightest, darkest), pixel| {
+            let br = pixel.0[0];
+            let brightest = if br > brightest { br } else { brightest };
+            let darkest = if br < darkest { br } else { darkest };
+            (brightest, darkest)
+        });
+    let bright_diff = brightest - darkest;
+    // Anything brighter than 90% between darkest and brightest counts as white
+    // Just a heuristic, works well for images that are black and white already
+    let threshold = darkest + (bright_diff / 10) * 9;
+
+    for pixel in img.pixels_mut() {
+        pixel.0[0] = if pixel.0[0] < threshold { BLACK } else { WHITE };
+    }
+}
+
+fn ordered(img: &mut GrayImage) {
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        // Scale the matrix from 0-63 to the middle of 64 evenly sized steps of 0-255
+        let threshold = BAYER[(y % 8) as usize][(x % 8) as usize] as u16 * 4 + 2;
+        pixel.0[0] = if (pixel.0[0] as u16) < threshold {
+            BLACK
+        } else {
+            WHITE
+        };
+    }
+}
+
+/// Round each pixel to black or white and spread the difference to the
+/// neighbouring pixels that haven't been processed yet
+fn diffuse_error(img: &mut GrayImage, kernel: &[(i32, i32, i32)], divisor: i32) {
+    let width = img.width() as i32;
+    let height = img.height() as i32;
+    let mut values: Vec<i32> = img.pixels().map(|pixel| pixel.0[0] as i32).collect();
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = (x + y * width) as usize;
+            let old = values[i];
+            let new = if old < 0x80 { BLACK } else { WHITE };
+            let error = old - new as i32;
+            values[i] = new as i32;
+
+            for (dx, dy, weight) in kernel {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx >= 0 && nx < width && ny < height {
+                    values[(nx + ny * width) as usize] += error * weight / divisor;
+                }
+            }
+        }
+    }
+
+    for (pixel, value) in img.pixels_mut().zip(values) {
+        pixel.0[0] = value as u8;
+    }
+}
@@ -0,0 +1,95 @@
+//! Keyboard controls for the games that run on the LED matrix
+//!
+//! The terminal is put into raw mode, so that keys are read as soon as they're
+//! pressed, without waiting for enter.
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{cursor, execute};
+
+/// What a key press does in a game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Left for the second player in Pong
+    SecondLeft,
+    /// Right for the second player in Pong
+    SecondRight,
+    /// Up for the second player in Pong across two modules
+    SecondUp,
+    /// Down for the second player in Pong across two modules
+    SecondDown,
+    /// Pause or resume
+    Pause,
+    Restart,
+    Quit,
+}
+
+pub const HELP: &str = "Arrows: Move, A/D: Second player, P/Space: Pause, R: Restart, Q/Esc: Quit";
+
+/// Raw mode, as long as it lives. Restores the terminal when dropped
+pub struct RawTerminal;
+
+impl RawTerminal {
+    pub fn enable() -> Self {
+        terminal::enable_raw_mode().expect("Failed to put the terminal into raw mode");
+        RawTerminal
+    }
+
+    /// Replace the current line, without moving on to the next one
+    pub fn show_line(&self, line: &str) {
+        let mut stdout = io::stdout();
+        execute!(
+            stdout,
+            cursor::MoveToColumn(0),
+            Clear(ClearType::CurrentLine)
+        )
+        .unwrap();
+        print!("{line}");
+        stdout.flush().unwrap();
+    }
+
+    /// Wait for a key that's used in games, up to the timeout
+    pub fn read_key(&self, timeout: Duration) -> Option<Key> {
+        if !event::poll(timeout).unwrap() {
+            return None;
+        }
+        match event::read().unwrap() {
+            // Windows also reports releasing keys
+            Event::Key(key) if key.kind == KeyEventKind::Press => map_key(key),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        println!();
+    }
+}
+
+/// Arrow keys for the first player, WASD for the second one in Pong
+fn map_key(key: KeyEvent) -> Option<Key> {
+    match key.code {
+        // Raw mode doesn't turn Ctrl+C into a signal anymore
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Key::Quit),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Char('a') | KeyCode::Char('A') => Some(Key::SecondLeft),
+        KeyCode::Char('d') | KeyCode::Char('D') => Some(Key::SecondRight),
+        KeyCode::Char('w') | KeyCode::Char('W') => Some(Key::SecondUp),
+        KeyCode::Char('s') | KeyCode::Char('S') => Some(Key::SecondDown),
+        KeyCode::Char('p') | KeyCode::Char('P') | KeyCode::Char(' ') => Some(Key::Pause),
+        KeyCode::Char('r') | KeyCode::Char('R') => Some(Key::Restart),
+        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => Some(Key::Quit),
+        _ => None,
+    }
+}
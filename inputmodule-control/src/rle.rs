@@ -0,0 +1,152 @@
+//! Run-length encoded framebuffer updates for the B1 display
+//!
+//! Only the bytes that changed since the previous frame are sent. Runs of the
+//! same byte, like large white areas, are sent as a single repetition.
+use image::{GrayImage, Luma};
+
+use crate::monochrome::{B1_HEIGHT, B1_WIDTH};
+
+/// Encoded bytes that fit into a single command, after the 2 byte offset
+const RLE_BYTES: usize = 59;
+
+const OP_LITERAL: u8 = 0x00;
+const OP_REPEAT: u8 = 0x01;
+const OP_SKIP: u8 = 0x02;
+
+/// Longest literal that fits into a single command, after its 2 byte header
+const MAX_LITERAL: usize = RLE_BYTES - 2;
+/// Longest repetition, the length is a single byte
+const MAX_REPEAT: usize = 0xFF;
+/// Shorter runs are cheaper to send as part of a literal than on their own
+const MIN_RUN: usize = 4;
+
+/// Pack a black and white image into the framebuffer layout of the display
+/// Column by column, 8 pixels per byte from top to bottom. 1 is black
+pub fn pack_framebuffer(img: &GrayImage) -> Vec<u8> {
+    assert!(img.width() == B1_WIDTH);
+    assert!(img.height() == B1_HEIGHT);
+
+    let mut framebuffer = vec![0; (B1_WIDTH * B1_HEIGHT / 8) as usize];
+    for (x, y, pixel) in img.enumerate_pixels() {
+        if pixel.0[0] < 0x80 {
+            let i = (x * B1_HEIGHT + y) as usize;
+            framebuffer[i / 8] |= 1 << (i % 8);
+        }
+    }
+    framebuffer
+}
+
+/// Turn the framebuffer layout of the display back into a black and white image
+pub fn unpack_framebuffer(framebuffer: &[u8]) -> GrayImage {
+    GrayImage::from_fn(B1_WIDTH, B1_HEIGHT, |x, y| {
+        let i = (x * B1_HEIGHT + y) as usize;
+        let black = framebuffer
+            .get(i / 8)
+            .is_some_and(|byte| byte & (1 << (i % 8)) != 0);
+        Luma([if black { 0x00 } else { 0xFF }])
+    })
+}
+
+/// Collects operations into commands, each starting with its offset
+struct Encoder {
+    commands: Vec<Vec<u8>>,
+    /// Operations of the command that's being built
+    ops: Vec<u8>,
+    /// Position in the framebuffer of the first operation
+    offset: usize,
+    /// Position in the framebuffer after the last operation
+    end: usize,
+}
+
+impl Encoder {
+    /// Add an operation that writes `len` bytes at `pos`
+    /// Skips over the unchanged bytes in between, or starts a new command if
+    /// that doesn't fit.
+    fn push(&mut self, pos: usize, op: &[u8], len: usize) {
+        let gap = pos - self.end;
+        let skip_len = if gap > 0 { 3 } else { 0 };
+        if self.ops.is_empty() || self.ops.len() + skip_len + op.len() > RLE_BYTES {
+            self.finish();
+            self.offset = pos;
+        } else if gap > 0 {
+            let gap = (gap as u16).to_le_bytes();
+            self.ops.extend_from_slice(&[OP_SKIP, gap[0], gap[1]]);
+        }
+        self.ops.extend_from_slice(op);
+        self.end = pos + len;
+    }
+
+    /// Add a literal, split up to fill the remaining space of the current command
+    fn literal(&mut self, mut pos: usize, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let skip_len = if pos > self.end { 3 } else { 0 };
+            let space = RLE_BYTES.saturating_sub(self.ops.len() + skip_len + 2);
+            let len = if self.ops.is_empty() || space == 0 {
+                bytes.len().min(MAX_LITERAL)
+            } else {
+                bytes.len().min(space)
+            };
+
+            let mut op = vec![OP_LITERAL, len as u8];
+            op.extend_from_slice(&bytes[..len]);
+            self.push(pos, &op, len);
+            pos += len;
+            bytes = &bytes[len..];
+        }
+    }
+
+    fn finish(&mut self) {
+        if !self.ops.is_empty() {
+            let mut command = (self.offset as u16).to_le_bytes().to_vec();
+            command.append(&mut self.ops);
+            self.commands.push(command);
+        }
+    }
+}
+
+/// Length of the run starting at `pos`, for which the condition holds
+fn run_len(pos: usize, len: usize, condition: impl Fn(usize) -> bool) -> usize {
+    (pos..len).take_while(|i| condition(*i)).count()
+}
+
+/// Encode the changes between two framebuffers
+/// Without a previous framebuffer, everything is encoded.
+/// Returns the arguments of each RleFramebuffer command.
+pub fn encode_rle(previous: Option<&[u8]>, next: &[u8]) -> Vec<Vec<u8>> {
+    let mut encoder = Encoder {
+        commands: vec![],
+        ops: vec![],
+        offset: 0,
+        end: 0,
+    };
+
+    let mut pos = 0;
+    let mut literal_start = 0;
+    while pos < next.len() {
+        let unchanged = previous
+            .map(|previous| run_len(pos, next.len(), |i| previous[i] == next[i]))
+            .unwrap_or(0);
+        let repeated = run_len(pos, next.len(), |i| next[i] == next[pos]).min(MAX_REPEAT);
+
+        if unchanged >= MIN_RUN {
+            encoder.literal(literal_start, &next[literal_start..pos]);
+            pos += unchanged;
+            literal_start = pos;
+        } else if repeated >= MIN_RUN {
+            encoder.literal(literal_start, &next[literal_start..pos]);
+            encoder.push(pos, &[OP_REPEAT, repeated as u8, next[pos]], repeated);
+            pos += repeated;
+            literal_start = pos;
+        } else {
+            pos += 1;
+            if pos - literal_start == MAX_LITERAL {
+                encoder.literal(literal_start, &next[literal_start..pos]);
+                literal_start = pos;
+            }
+        }
+    }
+    encoder.literal(literal_start, &next[literal_start..pos]);
+    encoder.finish();
+
+    encoder.commands
+}
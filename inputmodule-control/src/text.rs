@@ -0,0 +1,169 @@
+//! Text layout for the LED Matrix
+//!
+//! Lays out strings of any length into a strip of rows, which can then be
+//! scrolled across the display one frame at a time.
+use crate::font::{convert_font, convert_small_font, convert_symbol};
+use crate::inputmodule::{HEIGHT, WIDTH};
+use crate::ledmatrix::{Font, ScrollDirection};
+
+/// One row of the strip, one brightness value per LED
+pub type Row = [u8; WIDTH];
+
+/// Full frame, indexed by x and then y
+pub type Frame = [[u8; HEIGHT]; WIDTH];
+
+/// Full frame in landscape orientation (34x9), indexed by x and then y
+pub type LandscapeFrame = [[u8; WIDTH]; HEIGHT];
+
+/// Single character or symbol, one byte per pixel, row by row
+struct Glyph {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Glyph {
+    fn new(font: Font, c: char) -> Self {
+        let c = c.to_ascii_uppercase();
+        match font {
+            Font::Standard => Self::from_5x6(convert_font(c)),
+            Font::Small => Glyph {
+                width: 3,
+                height: 5,
+                pixels: convert_small_font(c),
+            },
+        }
+    }
+
+    fn from_5x6(pixels: Vec<u8>) -> Self {
+        Glyph {
+            width: 5,
+            height: 6,
+            pixels,
+        }
+    }
+
+    fn is_on(&self, x: usize, y: usize) -> bool {
+        self.pixels[x + y * self.width] == 1
+    }
+}
+
+/// Split text into glyphs
+/// Symbols from the symbol table can be embedded by name, like "{sun}"
+fn glyphs(text: &str, font: Font) -> Vec<Glyph> {
+    let mut glyphs = vec![];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '{' {
+            let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+            glyphs.push(Glyph::from_5x6(convert_symbol(&name)));
+        } else {
+            glyphs.push(Glyph::new(font, c));
+        }
+    }
+    glyphs
+}
+
+/// Lay out text into a strip of rows, in the order that they scroll onto the display
+///
+/// Vertical: Characters are stacked on top of each other, centered horizontally.
+/// Horizontal: Characters are rotated by 90 degrees, so that the text reads
+/// from bottom to top, with the top of the characters on the left side.
+pub fn layout(text: &str, font: Font, direction: ScrollDirection) -> Vec<Row> {
+    let mut strip = vec![];
+    for glyph in glyphs(text, font) {
+        match direction {
+            ScrollDirection::Vertical => {
+                let x_offset = (WIDTH - glyph.width) / 2;
+                for y in 0..glyph.height {
+                    let mut row = [0; WIDTH];
+                    for x in 0..glyph.width {
+                        if glyph.is_on(x, y) {
+                            row[x_offset + x] = 0xFF;
+                        }
+                    }
+                    strip.push(row);
+                }
+            }
+            ScrollDirection::Horizontal => {
+                let y_offset = (WIDTH - glyph.height) / 2;
+                for x in 0..glyph.width {
+                    let mut row = [0; WIDTH];
+                    for y in 0..glyph.height {
+                        if glyph.is_on(x, y) {
+                            row[y_offset + y] = 0xFF;
+                        }
+                    }
+                    strip.push(row);
+                }
+            }
+        }
+        // One empty row/column between characters
+        strip.push([0; WIDTH]);
+    }
+    strip
+}
+
+/// Strip to scroll, with a blank screen in front of the text
+/// When looping around, the text leaves the screen fully before it comes back.
+pub fn scroll_strip(text: &str, font: Font, direction: ScrollDirection) -> Vec<Row> {
+    let mut strip = vec![[0; WIDTH]; HEIGHT];
+    strip.extend(layout(text, font, direction));
+    strip
+}
+
+/// Render the visible part of the strip when scrolled by `offset` rows
+///
+/// The strip wraps around at the end. A fractional offset blends two adjacent
+/// rows together, which makes slow scrolling look smooth.
+pub fn scroll_frame(strip: &[Row], offset: f32, direction: ScrollDirection) -> Frame {
+    let mut frame = [[0; HEIGHT]; WIDTH];
+    let whole = offset.floor() as usize;
+    let fraction = offset - offset.floor();
+
+    for y in 0..HEIGHT {
+        // Vertical scrolling brings new rows in at the bottom,
+        // horizontal scrolling at the top
+        let row = match direction {
+            ScrollDirection::Vertical => whole + y,
+            ScrollDirection::Horizontal => whole + HEIGHT - 1 - y,
+        };
+        let current = strip[row % strip.len()];
+        let next = strip[(row + 1) % strip.len()];
+        for x in 0..WIDTH {
+            let blended = (current[x] as f32) * (1.0 - fraction) + (next[x] as f32) * fraction;
+            frame[x][y] = blended.round() as u8;
+        }
+    }
+    frame
+}
+
+/// Rotate a landscape frame into the portrait orientation of the display
+///
+/// Same orientation as the sideways LOTUS pattern. The left side of the
+/// landscape frame ends up at the bottom of the display.
+pub fn rotate_landscape(landscape: &LandscapeFrame) -> Frame {
+    let mut frame = [[0; HEIGHT]; WIDTH];
+    for (x, col) in landscape.iter().enumerate() {
+        for (y, brightness) in col.iter().enumerate() {
+            frame[y][HEIGHT - 1 - x] = *brightness;
+        }
+    }
+    frame
+}
+
+/// Lay out text in landscape, reading from left to right
+///
+/// Centered if it fits, otherwise the end is cut off.
+pub fn landscape_text(text: &str, font: Font) -> LandscapeFrame {
+    let mut columns = layout(text, font, ScrollDirection::Horizontal);
+    // No need for a gap after the last character
+    columns.pop();
+
+    let mut frame = [[0; WIDTH]; HEIGHT];
+    let x_offset = HEIGHT.saturating_sub(columns.len()) / 2;
+    for (x, col) in columns.iter().take(HEIGHT).enumerate() {
+        frame[x_offset + x] = *col;
+    }
+    frame
+}
@@ -0,0 +1,190 @@
+//! Video decoding
+//!
+//! Animated GIFs and PNGs are decoded in Rust. All other formats are decoded
+//! by ffmpeg, which needs to be installed separately.
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, DynamicImage, Frames, GrayImage};
+
+/// Frame rate to assume if ffprobe can't tell
+const DEFAULT_FPS: f64 = 30.0;
+
+/// Single frame of a video, scaled to the size of the display
+#[derive(Clone)]
+pub struct VideoFrame {
+    /// When to show the frame, relative to the start of the video
+    pub timestamp: Duration,
+    /// How long to show the frame for
+    pub duration: Duration,
+    pub image: GrayImage,
+}
+
+/// Decode a video and convert every frame, for example scaling it to the size of the display
+pub fn decode_video(
+    path: &str,
+    convert: impl FnMut(DynamicImage) -> GrayImage + 'static,
+) -> Box<dyn Iterator<Item = VideoFrame>> {
+    if let Some(frames) = decode_animation(path) {
+        Box::new(animation_frames(frames, convert))
+    } else {
+        Box::new(FfmpegDecoder::new(path, convert))
+    }
+}
+
+/// Decode the frames of an animated GIF or PNG
+/// None for other formats and for PNGs that aren't animated
+pub fn decode_animation(path: &str) -> Option<Frames<'static>> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(|ext| ext.to_ascii_lowercase());
+    let file = BufReader::new(File::open(path).unwrap());
+
+    match extension.as_deref() {
+        Some("gif") => Some(GifDecoder::new(file).unwrap().into_frames()),
+        Some("png") | Some("apng") => {
+            let png = PngDecoder::new(file).unwrap();
+            if png.is_apng() {
+                Some(png.apng().into_frames())
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Convert the frames of an animation, keeping each on screen for as long as its delay
+pub fn animation_frames(
+    frames: Frames<'static>,
+    mut convert: impl FnMut(DynamicImage) -> GrayImage,
+) -> impl Iterator<Item = VideoFrame> {
+    let mut timestamp = Duration::ZERO;
+    frames.map(move |frame| {
+        let frame = frame.unwrap();
+        let duration = Duration::from(frame.delay());
+        let image = convert(DynamicImage::from(frame.into_buffer()));
+
+        let video_frame = VideoFrame {
+            timestamp,
+            duration,
+            image,
+        };
+        timestamp += duration;
+        video_frame
+    })
+}
+
+/// Let ffmpeg decode the video and read greyscale frames in their original size from it
+/// Each frame is a PGM image, which says how large it is.
+struct FfmpegDecoder<F> {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+    convert: F,
+    frame_duration: Duration,
+    frame_index: u32,
+}
+
+impl<F: FnMut(DynamicImage) -> GrayImage> FfmpegDecoder<F> {
+    fn new(path: &str, convert: F) -> Self {
+        let fps = frame_rate(path);
+        let mut child = Command::new("ffmpeg")
+            .args(["-v", "error", "-i", path, "-an"])
+            .args(["-pix_fmt", "gray", "-f", "image2pipe", "-c:v", "pgm", "-"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Failed to run ffmpeg. It's required to play videos other than GIF and APNG");
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        FfmpegDecoder {
+            child,
+            stdout,
+            convert,
+            frame_duration: Duration::from_secs_f64(1.0 / fps),
+            frame_index: 0,
+        }
+    }
+}
+
+impl<F: FnMut(DynamicImage) -> GrayImage> Iterator for FfmpegDecoder<F> {
+    type Item = VideoFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Fails at the end of the video
+        let frame = read_pgm(&mut self.stdout)?;
+
+        let timestamp = self.frame_duration * self.frame_index;
+        self.frame_index += 1;
+        Some(VideoFrame {
+            timestamp,
+            duration: self.frame_duration,
+            image: (self.convert)(DynamicImage::from(frame)),
+        })
+    }
+}
+
+/// Read a binary PGM image with 8 bits per pixel, like "P5\n640 480\n255\n" and the pixels
+fn read_pgm(reader: &mut impl BufRead) -> Option<GrayImage> {
+    if read_pgm_token(reader)? != "P5" {
+        return None;
+    }
+    let width = read_pgm_token(reader)?.parse().ok()?;
+    let height = read_pgm_token(reader)?.parse().ok()?;
+    if read_pgm_token(reader)? != "255" {
+        return None;
+    }
+    let mut pixels = vec![0; width as usize * height as usize];
+    reader.read_exact(&mut pixels).ok()?;
+    GrayImage::from_raw(width, height, pixels)
+}
+
+/// Next word of the header, including the single whitespace after it
+fn read_pgm_token(reader: &mut impl BufRead) -> Option<String> {
+    let mut token = String::new();
+    for byte in reader.bytes() {
+        let byte = byte.ok()?;
+        if !byte.is_ascii_whitespace() {
+            token.push(byte as char);
+        } else if !token.is_empty() {
+            return Some(token);
+        }
+    }
+    None
+}
+
+impl<F> Drop for FfmpegDecoder<F> {
+    fn drop(&mut self) {
+        // Might have stopped before the end of the video
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Get the frame rate of the first video stream with ffprobe
+fn frame_rate(path: &str) -> f64 {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=r_frame_rate"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1", path])
+        .output();
+
+    // Formatted as a fraction, like 30000/1001
+    let fps = output.ok().and_then(|output| {
+        let rate = String::from_utf8(output.stdout).ok()?;
+        let (num, den) = rate.trim().split_once('/')?;
+        let fps = num.parse::<f64>().ok()? / den.parse::<f64>().ok()?;
+        (fps.is_finite() && fps > 0.0).then_some(fps)
+    });
+
+    fps.unwrap_or_else(|| {
+        println!("Couldn't find out the frame rate, assuming {DEFAULT_FPS} FPS");
+        DEFAULT_FPS
+    })
+}
@@ -143,8 +143,10 @@ use usbd_serial::{SerialPort, USB_CLASS_CDC};
 use core::fmt::Write;
 use heapless::String;
 
+use fl16_inputmodules::clock::{ClockFace, DeviceTime};
 use fl16_inputmodules::control::*;
-use fl16_inputmodules::games::{pong, snake};
+use fl16_inputmodules::games::high_scores::HighScores;
+use fl16_inputmodules::games::{self, pong, snake, tetris};
 use fl16_inputmodules::matrix::*;
 use fl16_inputmodules::patterns::*;
 use fl16_inputmodules::serialnum::{device_release, get_serialnum};
@@ -240,15 +242,26 @@ fn main() -> ! {
 
     let mut state = LedmatrixState {
         grid: percentage(0),
-        col_buffer: Grid::default(),
+        back_buffer: Grid::default(),
+        present_pending: None,
+        frame: 0,
         animate: false,
+        scroll_offset: 0,
         brightness: 51, // Default to 51/255 = 20% brightness
         sleeping: SleepState::Awake,
         game: None,
+        game_paused: false,
+        game_paused_by_sleep: false,
+        game_step_period: None,
+        high_scores: HighScores::load(),
+        new_high_score: None,
         animation_period: 31_250, // 31,250 us = 32 FPS
         pwm_freq: PwmFreqArg::P29k,
         debug_mode: false,
         upcoming_frames: None,
+        scroll_strip: ScrollStrip::default(),
+        time: None,
+        clock: None,
     };
     state.debug_mode = dip1.is_low().unwrap();
     if show_startup_animation(&state) {
@@ -366,6 +379,11 @@ fn main() -> ! {
         }
         last_usb_suspended = usb_suspended;
 
+        // Text that the module scrolls by itself and the clock keep it awake,
+        // even without commands
+        if matches!(state.upcoming_frames, Some(Animation::Scroll(_))) || state.clock.is_some() {
+            sleep_timer = timer.get_counter().ticks();
+        }
         // Go to sleep after the timer has run out
         if timer.get_counter().ticks() > sleep_timer + SLEEP_TIMEOUT && !state.debug_mode {
             sleep_reason = assign_sleep_reason(
@@ -401,12 +419,27 @@ fn main() -> ! {
                     state.grid = Grid::default();
                 }
             }
+            if let (Some(clock), Some(time)) = (state.clock, state.time) {
+                let (hours, minutes, _) = time.time_of_day(timer.get_counter().ticks());
+                state.grid = display_clock(hours, minutes, clock.face == ClockFace::Landscape);
+            }
+
+            let present = state.present_pending.take();
+            if present.is_some() {
+                state.grid = state.back_buffer.clone();
+                state.back_buffer = percentage(0);
+            }
 
             fill_grid_pixels(&state, &mut matrix);
+            state.frame = state.frame.wrapping_add(1);
+            if present == Some(true) {
+                // Lets the host wait for the frame, to stay in step with it
+                let mut response: [u8; 32] = [0; 32];
+                response[0..4].copy_from_slice(&state.frame.to_le_bytes());
+                let _ = serial.write(&response);
+            }
             if state.animate {
-                for x in 0..WIDTH {
-                    state.grid.0[x].rotate_right(1);
-                }
+                state.scroll_offset = (state.scroll_offset + 1) % HEIGHT;
             }
             animation_timer = timer.get_counter().ticks();
         }
@@ -447,6 +480,15 @@ fn main() -> ! {
                         (Some(c @ Command::BootloaderReset), _) => {
                             handle_command(&c, &mut state, &mut matrix, random);
                         }
+                        // Only look at what's shown or the state of the game, without waking
+                        // up or stopping animations. The host may keep polling them
+                        (Some(c @ (Command::GetGrid(_) | Command::GameStatus)), _) => {
+                            if let Some(response) =
+                                handle_command(&c, &mut state, &mut matrix, random)
+                            {
+                                let _ = serial.write(&response);
+                            };
+                        }
                         (Some(command), _) => {
                             if let Command::Sleep(go_sleeping) = command {
                                 sleep_reason = assign_sleep_reason(
@@ -485,6 +527,11 @@ fn main() -> ! {
                             // Very easy way to keep the device from going to sleep
                             sleep_timer = timer.get_counter().ticks();
 
+                            if let Command::SetTime(hours, minutes, seconds) = command {
+                                let now = timer.get_counter().ticks();
+                                state.time = Some(DeviceTime::new(hours, minutes, seconds, now));
+                            }
+
                             if let Some(response) =
                                 handle_command(&command, &mut state, &mut matrix, random)
                             {
@@ -525,31 +572,36 @@ fn main() -> ! {
         }
 
         // Handle game state
-        let game_step_diff = match state.game {
-            Some(GameState::Pong(ref pong_state)) => 100_000 - 5_000 * pong_state.speed,
-            Some(GameState::Snake(_)) => 500_000,
-            Some(GameState::GameOfLife(_)) => 500_000,
-            _ => 500_000,
-        };
-        if timer.get_counter().ticks() > game_timer + game_step_diff {
+        let game_step_diff = games::step_period(&state);
+        if state.game_paused {
+            game_timer = timer.get_counter().ticks();
+        } else if timer.get_counter().ticks() > game_timer + game_step_diff {
             let random = get_random_byte(&rosc);
+            // Only logged in debug mode, it'd get mixed up with responses to GameStatus
             match state.game {
                 Some(GameState::GameOfLife(_)) => {
-                    let _ = serial.write(b"GOL Game step\r\n");
+                    if state.debug_mode {
+                        let _ = serial.write(b"GOL Game step\r\n");
+                    }
                     game_of_life::game_step(&mut state, random);
                 }
                 Some(GameState::Pong(_)) => {
-                    let _ = serial.write(b"Pong Game step\r\n");
+                    if state.debug_mode {
+                        let _ = serial.write(b"Pong Game step\r\n");
+                    }
                     pong::game_step(&mut state, random);
                 }
+                Some(GameState::Tetris(_)) => {
+                    if state.debug_mode {
+                        let _ = serial.write(b"Tetris Game step\r\n");
+                    }
+                    tetris::game_step(&mut state, random);
+                }
                 Some(GameState::Snake(_)) => {
-                    let _ = serial.write(b"Snake Game step\r\n");
                     let (direction, game_over, points, (x, y)) =
                         snake::game_step(&mut state, random);
 
-                    if game_over {
-                        // TODO: Show score
-                    } else {
+                    if state.debug_mode {
                         let mut text: String<64> = String::new();
                         write!(
                             &mut text,
@@ -557,11 +609,13 @@ fn main() -> ! {
                             direction, game_over, points, x, y
                         )
                         .unwrap();
+                        let _ = serial.write(b"Snake Game step\r\n");
                         let _ = serial.write(text.as_bytes());
                     }
                 }
                 None => {}
             }
+            games::high_score_step(&mut state);
             game_timer = timer.get_counter().ticks();
         }
     }
@@ -622,6 +676,7 @@ fn handle_sleep(
         (SleepState::Awake, None) => (),
         (SleepState::Awake, Some(sleep_reason)) => {
             state.sleeping = SleepState::Sleeping((state.grid.clone(), state.brightness));
+            games::pause_for_sleep(state);
             // Slowly decrease brightness
             if dyn_sleep_mode(state) == SleepMode::Fading {
                 let mut brightness = state.brightness;
@@ -659,6 +714,7 @@ fn handle_sleep(
             // Restore back grid before sleeping
             state.sleeping = SleepState::Awake;
             state.grid = old_grid;
+            games::resume_after_sleep(state);
             fill_grid_pixels(state, matrix);
 
             // Power LED controller back on